@@ -0,0 +1,61 @@
+// cargo bench --bench lines [-- <line count>]
+//
+// measures the line mesh path used by the view synchronization: building the
+// meshes once and then updating all of them in place after every point moved
+
+use std::{env, time::Instant};
+
+use bevy::{math::Vec2, render::mesh::Mesh};
+use layer_home_planner::{document::PlanDocument, LineShape};
+
+const DEFAULT_LINES: usize = 50_000;
+const ROUNDS: usize = 10;
+const WIDTH: f32 = 0.02;
+
+fn main() {
+    let count = env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_LINES);
+    let mut document = PlanDocument::default();
+    let mut previous = document.add_point(Vec2::ZERO);
+    for index in 1..=count {
+        let position = Vec2::new((index % 100) as f32, (index / 100) as f32);
+        previous = document.extend(previous, position).unwrap().0;
+    }
+    document.take_changes();
+
+    let start = Instant::now();
+    let mut meshes: Vec<Mesh> = document
+        .lines()
+        .map(|(id, _)| {
+            let (a, b) = document.line_positions(id).unwrap();
+            LineShape::new(a, b, WIDTH).into()
+        })
+        .collect();
+    report("build", meshes.len(), start);
+
+    let ids: Vec<_> = document.points().map(|(id, _)| id).collect();
+    for round in 0..ROUNDS {
+        let offset = Vec2::splat(round as f32 * 0.01);
+        for id in &ids {
+            let position = document.position(*id).unwrap();
+            document.move_point(*id, position + offset);
+        }
+        let start = Instant::now();
+        let changes = document.take_changes();
+        let mut updated = 0;
+        for (mesh, id) in meshes.iter_mut().zip(changes.lines) {
+            let (a, b) = document.line_positions(id).unwrap();
+            LineShape::new(a, b, WIDTH).update(mesh);
+            updated += 1;
+        }
+        report("update", updated, start);
+    }
+}
+
+fn report(name: &str, lines: usize, start: Instant) {
+    let elapsed = start.elapsed();
+    let per_line = elapsed.as_nanos() / lines.max(1) as u128;
+    println!("{name}: {lines} lines in {elapsed:.2?} ({per_line} ns per line)");
+}
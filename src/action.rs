@@ -1,16 +1,21 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, fmt};
 
 use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
 
 use crate::{
+    clipboard::Clipboard,
+    notification::Notification,
     plan::{
-        line::{Line, LineAssets, LineBundle},
-        point::{Point, PointAssets, PointBundle},
-        PlanMode, TrackMode,
+        document::{DoorId, LabelId, LineId, PlanDocument, PointId},
+        repair,
+        transform::{self, TransformKind, Transformation},
+        Pivot, PlanMode, Selection, TrackMode, TrackOrigins,
     },
     AppSet,
 };
 
+const HISTORY_LENGTH: usize = 100;
+
 #[derive(ScheduleLabel, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct ActionSchedule;
 
@@ -22,22 +27,61 @@ pub struct ActionPlugin;
 impl Plugin for ActionPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ActionQueue>()
+            .init_resource::<History>()
+            .add_event::<ActionError>()
             .add_system(process_actions.in_set(AppSet::Action))
             .init_schedule(ActionSchedule)
             .add_systems_to_schedule(
                 ActionSchedule,
                 (
+                    handle_add_door_action,
+                    handle_add_label_action,
+                    handle_copy_action,
                     handle_create_action,
+                    handle_cut_action,
                     handle_delete_action,
+                    handle_delete_door_action,
+                    handle_delete_label_action,
+                    handle_delete_line_action,
+                    handle_discard_action,
+                    handle_dissolve_action,
+                )
+                    .in_set(ActionSet),
+            )
+            // split up, tuples of systems are limited in length
+            .add_systems_to_schedule(
+                ActionSchedule,
+                (
+                    handle_draw_action,
+                    handle_duplicate_action,
                     handle_extend_action,
+                    handle_load_action,
                     handle_merge_action,
                     handle_move_action,
+                    handle_paste_action,
+                    handle_redo_action,
+                )
+                    .in_set(ActionSet),
+            )
+            .add_systems_to_schedule(
+                ActionSchedule,
+                (
+                    handle_repair_action,
+                    handle_restore_action,
                     handle_select_action,
+                    handle_select_all_action,
+                    handle_split_action,
+                    handle_start_action,
+                    handle_toggle_select_action,
                     handle_track_action,
+                    handle_track_transform_action,
+                    handle_transform_action,
+                    handle_undo_action,
                     handle_unselect_action,
                 )
                     .in_set(ActionSet),
             )
+            .add_system_to_schedule(ActionSchedule, sync_selection.after(ActionSet))
             .add_system_to_schedule(ActionSchedule, apply_system_buffers.after(ActionSet));
     }
 }
@@ -48,137 +92,755 @@ pub struct ActionQueue(VecDeque<Action>);
 #[derive(Resource, Deref, DerefMut)]
 struct CurrentAction(Action);
 
+// actions on a selected point apply to every selected point
 pub enum Action {
+    // the door is centered where the position meets the line
+    AddDoor(LineId, Vec2, f32),
+    AddLabel(Vec2, String),
+    Copy,
     Create,
-    Delete(Entity),
-    Extend(Entity),
-    Merge(Entity, Entity),
-    Move(Entity, Vec2),
-    Select(Entity),
-    Track(Entity),
+    Cut,
+    Delete(PointId),
+    DeleteDoor(DoorId),
+    DeleteLabel(LabelId),
+    DeleteLine(LineId),
+    Discard(PointId),
+    Dissolve(PointId),
+    Draw(Option<PointId>, Vec<Vec2>),
+    Duplicate(Vec2),
+    Extend(PointId),
+    Load(PlanDocument),
+    Merge(PointId, PointId),
+    Move(PointId, Vec2),
+    Paste(Vec2),
+    Redo,
+    Repair,
+    Restore(PointId),
+    Select(PointId),
+    SelectAll,
+    Split(LineId, Vec2),
+    Start(Vec2),
+    ToggleSelect(PointId),
+    Track(PointId),
+    TrackTransform(TransformKind, Vec2),
+    Transform(Transformation),
+    Undo,
     Unselect,
 }
 
+impl Action {
+    // actions can be queued before an earlier one removes what they refer to
+    fn validate(
+        &self,
+        document: &PlanDocument,
+        mode: &PlanMode,
+        history: &History,
+    ) -> Result<(), ActionError> {
+        let exists = |id: PointId| match document.point(id) {
+            Some(_) => Ok(()),
+            None => Err(ActionError::MissingPoint(id)),
+        };
+        let line_exists = |id: LineId| match document.line(id) {
+            Some(_) => Ok(()),
+            None => Err(ActionError::MissingLine(id)),
+        };
+        match self {
+            Action::AddDoor(_, position, _) if !position.is_finite() => {
+                Err(ActionError::InvalidPosition)
+            }
+            Action::AddDoor(_, _, width) if !(*width > 0.0 && width.is_finite()) => {
+                Err(ActionError::InvalidWidth)
+            }
+            Action::AddDoor(id, _, _) => line_exists(*id),
+            Action::AddLabel(position, _) if !position.is_finite() => {
+                Err(ActionError::InvalidPosition)
+            }
+            Action::AddLabel(_, text) if text.trim().is_empty() => Err(ActionError::EmptyLabel),
+            Action::AddLabel(_, _) => Ok(()),
+            Action::DeleteDoor(id) => match document.door(*id) {
+                Some(_) => Ok(()),
+                None => Err(ActionError::MissingDoor(*id)),
+            },
+            Action::DeleteLabel(id) => match document.label(*id) {
+                Some(_) => Ok(()),
+                None => Err(ActionError::MissingLabel(*id)),
+            },
+            Action::Copy
+            | Action::Create
+            | Action::Cut
+            | Action::Load(_)
+            | Action::Repair
+            | Action::SelectAll
+            | Action::Unselect => Ok(()),
+            Action::Delete(id)
+            | Action::Discard(id)
+            | Action::Extend(id)
+            | Action::Restore(id)
+            | Action::Select(id)
+            | Action::ToggleSelect(id)
+            | Action::Track(id) => exists(*id),
+            Action::DeleteLine(id) => line_exists(*id),
+            Action::Dissolve(id) => match document.dissolvable(*id) {
+                Some(_) => Ok(()),
+                None => exists(*id).and(Err(ActionError::NotDissolvable(*id))),
+            },
+            Action::Split(id, position) => match position.is_finite() {
+                true => line_exists(*id),
+                false => Err(ActionError::InvalidPosition),
+            },
+            Action::Draw(start, positions) => {
+                if let Some(start) = start {
+                    exists(*start)?;
+                }
+                match positions.iter().all(|position| position.is_finite()) {
+                    true => Ok(()),
+                    false => Err(ActionError::InvalidPosition),
+                }
+            }
+            Action::Merge(from, into) if from == into => Err(ActionError::SamePoint(*from)),
+            Action::Merge(from, into) => exists(*from).and(exists(*into)),
+            Action::Duplicate(position)
+            | Action::Paste(position)
+            | Action::Start(position)
+            | Action::TrackTransform(_, position) => match position.is_finite() {
+                true => Ok(()),
+                false => Err(ActionError::InvalidPosition),
+            },
+            Action::Transform(transformation) => match transformation.is_valid() {
+                true => Ok(()),
+                false => Err(ActionError::InvalidTransformation),
+            },
+            Action::Move(id, position) => match position.is_finite() {
+                true => exists(*id),
+                false => Err(ActionError::InvalidPosition),
+            },
+            // the tracked edit is not in the history yet
+            Action::Redo | Action::Undo if matches!(mode, PlanMode::Track(_, _)) => {
+                Err(ActionError::Tracking)
+            }
+            Action::Redo if history.redo.is_empty() => Err(ActionError::NothingToRedo),
+            Action::Undo if history.undo.is_empty() => Err(ActionError::NothingToUndo),
+            Action::Redo | Action::Undo => Ok(()),
+        }
+    }
+}
+
+// sent for every action that was skipped
+#[derive(Clone, Copy, Debug)]
+pub enum ActionError {
+    MissingPoint(PointId),
+    MissingLine(LineId),
+    MissingDoor(DoorId),
+    MissingLabel(LabelId),
+    SamePoint(PointId),
+    NotDissolvable(PointId),
+    InvalidPosition,
+    InvalidTransformation,
+    InvalidWidth,
+    EmptyLabel,
+    Tracking,
+    NothingToUndo,
+    NothingToRedo,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::MissingPoint(id) => write!(f, "point {id} no longer exists"),
+            ActionError::MissingLine(id) => write!(f, "line {id} no longer exists"),
+            ActionError::MissingDoor(id) => write!(f, "door {id} no longer exists"),
+            ActionError::MissingLabel(id) => write!(f, "label {id} no longer exists"),
+            ActionError::NotDissolvable(id) => {
+                write!(f, "point {id} does not join exactly two lines")
+            }
+            ActionError::SamePoint(id) => write!(f, "point {id} cannot be merged with itself"),
+            ActionError::InvalidPosition => write!(f, "position is not a finite number"),
+            ActionError::InvalidTransformation => {
+                write!(f, "transformation would collapse the selection")
+            }
+            ActionError::InvalidWidth => write!(f, "width is not a positive length"),
+            ActionError::EmptyLabel => write!(f, "label has no text"),
+            ActionError::Tracking => write!(f, "the current edit has to be finished first"),
+            ActionError::NothingToUndo => write!(f, "nothing to undo"),
+            ActionError::NothingToRedo => write!(f, "nothing to redo"),
+        }
+    }
+}
+
+// snapshots of the document taken before every edit
+#[derive(Resource, Default)]
+pub struct History {
+    undo: Vec<PlanDocument>,
+    redo: Vec<PlanDocument>,
+    // the document before the points being tracked were picked up
+    pending: Option<PlanDocument>,
+}
+
+impl History {
+    // only actions that changed the document are recorded, tracking is
+    // recorded as a whole once the points are dropped and not at all when
+    // it is cancelled
+    fn update(
+        &mut self,
+        document: &PlanDocument,
+        mode: &PlanMode,
+        before: PlanDocument,
+        pending: Option<PlanDocument>,
+    ) {
+        let tracking = matches!(mode, PlanMode::Track(_, _));
+        let changed = !document.same_elements(&before);
+        match (pending, tracking) {
+            (None, true) => self.pending = Some(before),
+            (None, false) => {
+                if changed {
+                    self.record(before);
+                }
+            }
+            // the next wall of a chain, the previous one is finished
+            (Some(origin), true) if changed => {
+                if !before.same_elements(&origin) {
+                    self.record(origin);
+                }
+                self.pending = Some(before);
+            }
+            (Some(origin), true) => self.pending = Some(origin),
+            (Some(origin), false) => {
+                if !document.same_elements(&origin) {
+                    self.record(origin);
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, document: PlanDocument) {
+        self.undo.push(document);
+        if self.undo.len() > HISTORY_LENGTH {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+}
+
 fn process_actions(world: &mut World) {
     world.resource_scope(|world, mut action_queue: Mut<ActionQueue>| {
         while let Some(action) = action_queue.pop_front() {
+            let document = world.resource::<PlanDocument>();
+            if let Err(error) = action.validate(document, world.resource(), world.resource()) {
+                world.send_event(error);
+                continue;
+            }
+            // undo and redo move through the history themselves
+            let undoing = matches!(action, Action::Undo | Action::Redo);
+            let before = world.resource::<PlanDocument>().clone();
             world.insert_resource(CurrentAction(action));
             world.run_schedule(ActionSchedule);
             world.remove_resource::<CurrentAction>();
+            if !undoing {
+                world.resource_scope(|world, mut history: Mut<History>| {
+                    let pending = history.pending.take();
+                    history.update(world.resource(), world.resource(), before, pending);
+                });
+            }
         }
     });
 }
 
+fn copy_selection(
+    document: &PlanDocument,
+    selection: &Selection,
+    clipboard: &mut Clipboard,
+    notifications: &mut EventWriter<Notification>,
+) {
+    let fragment = document.extract(&selection.points);
+    let (points, lines) = (fragment.points().count(), fragment.lines().count());
+    clipboard.write(fragment.to_string());
+    notifications.send(Notification::info(format!(
+        "Copied {points} points and {lines} lines"
+    )));
+}
+
+// the fragment is tracked by its first point until it is placed
+fn place_fragment(
+    fragment: &PlanDocument,
+    position: Vec2,
+    document: &mut PlanDocument,
+    selection: &mut Selection,
+    mode: &mut PlanMode,
+) {
+    let Some((_, anchor)) = fragment.points().next() else {
+        return;
+    };
+    let ids = document.insert(fragment, position - anchor.position());
+    *mode = PlanMode::Track(ids[0], TrackMode::Place);
+    selection.points = ids;
+}
+
+fn restore_origins(origins: &TrackOrigins, document: &mut PlanDocument) {
+    for (id, origin) in origins.iter() {
+        document.move_point(*id, *origin);
+    }
+}
+
+fn handle_add_door_action(action: Res<CurrentAction>, mut document: ResMut<PlanDocument>) {
+    if let Action::AddDoor(id, position, width) = **action {
+        let Some((a, b)) = document.line_positions(id) else {
+            return;
+        };
+        let direction = b - a;
+        let offset = match direction.length_squared() {
+            length if length > 0.0 => (position - a).dot(direction) / length,
+            _ => 0.5,
+        };
+        document.add_door(id, offset, width);
+    }
+}
+
+fn handle_add_label_action(action: Res<CurrentAction>, mut document: ResMut<PlanDocument>) {
+    if let Action::AddLabel(position, text) = &**action {
+        document.add_label(*position, text);
+    }
+}
+
+fn handle_copy_action(
+    action: Res<CurrentAction>,
+    document: Res<PlanDocument>,
+    selection: Res<Selection>,
+    mut clipboard: NonSendMut<Clipboard>,
+    mut notifications: EventWriter<Notification>,
+) {
+    if let Action::Copy = **action {
+        copy_selection(&document, &selection, &mut clipboard, &mut notifications);
+    }
+}
+
 fn handle_create_action(
     action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
     mut mode: ResMut<PlanMode>,
-    assets: Res<PointAssets>,
-    mut commands: Commands,
 ) {
     if let Action::Create = **action {
-        let entity = commands.spawn(PointBundle::empty(&assets)).id();
-        *mode = PlanMode::Track(entity, TrackMode::Place);
+        let id = document.add_point(Vec2::ZERO);
+        *mode = PlanMode::Track(id, TrackMode::Place);
+    }
+}
+
+fn handle_cut_action(
+    action: Res<CurrentAction>,
+    selection: Res<Selection>,
+    mut document: ResMut<PlanDocument>,
+    mut clipboard: NonSendMut<Clipboard>,
+    mut mode: ResMut<PlanMode>,
+    mut notifications: EventWriter<Notification>,
+) {
+    if let Action::Cut = **action {
+        copy_selection(&document, &selection, &mut clipboard, &mut notifications);
+        for id in &selection.points {
+            document.remove_point(*id);
+        }
+        *mode = PlanMode::Default;
     }
 }
 
 fn handle_delete_action(
     action: Res<CurrentAction>,
-    mut point_query: Query<&mut Point>,
-    line_query: Query<&Line>,
+    selection: Res<Selection>,
+    mut document: ResMut<PlanDocument>,
     mut mode: ResMut<PlanMode>,
-    mut commands: Commands,
 ) {
-    if let Action::Delete(point_entity) = **action {
-        let point = point_query.get(point_entity).unwrap();
-        let lines_entities = point.lines.clone();
-        for line_entity in lines_entities {
-            let line = line_query.get(line_entity).unwrap();
-            let other_point_entity = line.other(point_entity).unwrap();
-            let mut other_point = point_query.get_mut(other_point_entity).unwrap();
-            let lines = &mut other_point.lines;
-            lines.remove(lines.iter().position(|e| *e == line_entity).unwrap());
-            commands.entity(line_entity).despawn();
+    if let Action::Delete(id) = **action {
+        let points = match selection.contains(id) {
+            true => selection.points.clone(),
+            false => vec![id],
+        };
+        for id in points {
+            document.remove_point(id);
         }
-        commands.entity(point_entity).despawn();
         *mode = PlanMode::Default;
     }
 }
 
+fn handle_delete_door_action(action: Res<CurrentAction>, mut document: ResMut<PlanDocument>) {
+    if let Action::DeleteDoor(id) = **action {
+        document.remove_door(id);
+    }
+}
+
+fn handle_delete_label_action(action: Res<CurrentAction>, mut document: ResMut<PlanDocument>) {
+    if let Action::DeleteLabel(id) = **action {
+        document.remove_label(id);
+    }
+}
+
+fn handle_delete_line_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::DeleteLine(id) = **action {
+        document.remove_line(id);
+        *mode = PlanMode::Default;
+    }
+}
+
+// cancels placing a point, the start of a chain goes along with it when it
+// was only added for this wall
+fn handle_discard_action(
+    action: Res<CurrentAction>,
+    history: Res<History>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Discard(id) = **action {
+        let start = match document.point(id).map(|point| point.lines()) {
+            Some([line]) => document.line(*line).and_then(|line| line.other(id)),
+            _ => None,
+        };
+        document.remove_point(id);
+        if let Some(start) = start {
+            let added = match &history.pending {
+                Some(origin) => origin.point(start).is_none(),
+                None => false,
+            };
+            let unused = document
+                .point(start)
+                .is_some_and(|point| point.lines().is_empty());
+            if added && unused {
+                document.remove_point(start);
+            }
+        }
+        *mode = PlanMode::Default;
+    }
+}
+
+fn handle_dissolve_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Dissolve(id) = **action {
+        document.dissolve_point(id);
+        *mode = PlanMode::Default;
+    }
+}
+
+fn handle_draw_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Draw(start, positions) = &**action {
+        let mut previous = *start;
+        for &position in positions {
+            previous = match previous {
+                Some(previous_id) => document.extend(previous_id, position).map(|(id, _)| id),
+                None => Some(document.add_point(position)),
+            };
+        }
+        if let Some(last_id) = previous {
+            *mode = PlanMode::Select(last_id);
+        }
+    }
+}
+
+fn handle_duplicate_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut selection: ResMut<Selection>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Duplicate(position) = **action {
+        let fragment = document.extract(&selection.points);
+        place_fragment(
+            &fragment,
+            position,
+            &mut document,
+            &mut selection,
+            &mut mode,
+        );
+    }
+}
+
 fn handle_extend_action(
     action: Res<CurrentAction>,
-    mut query: Query<&mut Point>,
+    mut document: ResMut<PlanDocument>,
     mut mode: ResMut<PlanMode>,
-    point_assets: Res<PointAssets>,
-    line_assets: Res<LineAssets>,
-    mut commands: Commands,
 ) {
-    if let Action::Extend(old_point_entity) = **action {
-        let new_point_entity = commands.spawn_empty().id();
-        let line_entity = commands
-            .spawn(LineBundle::new(
-                old_point_entity,
-                new_point_entity,
-                &line_assets,
-            ))
-            .id();
-        commands
-            .entity(new_point_entity)
-            .insert(PointBundle::from_line(line_entity, &point_assets));
-        let mut old_point = query.get_mut(old_point_entity).unwrap();
-        old_point.lines.push(line_entity);
-        *mode = PlanMode::Track(new_point_entity, TrackMode::Place);
+    if let Action::Extend(old_id) = **action {
+        let position = document.position(old_id).unwrap_or_default();
+        if let Some((new_id, _)) = document.extend(old_id, position) {
+            *mode = PlanMode::Track(new_id, TrackMode::Place);
+        }
+    }
+}
+
+fn handle_load_action(
+    mut action: ResMut<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Load(loaded) = &mut **action {
+        document.replace(std::mem::take(loaded));
+        *mode = PlanMode::Default;
     }
 }
 
 fn handle_merge_action(
     action: Res<CurrentAction>,
-    mut point_query: Query<&mut Point>,
-    mut line_query: Query<&mut Line>,
+    mut document: ResMut<PlanDocument>,
     mut mode: ResMut<PlanMode>,
-    mut commands: Commands,
 ) {
-    if let Action::Merge(old_point_entity, new_point_entity) = **action {
-        let old_point = point_query.get(old_point_entity).unwrap();
-        let lines_entities = old_point.lines.clone();
-        let mut new_point = point_query.get_mut(new_point_entity).unwrap();
-        for line_entity in lines_entities {
-            let mut line = line_query.get_mut(line_entity).unwrap();
-            line.replace(old_point_entity, new_point_entity);
-            new_point.lines.push(line_entity);
-        }
-        commands.entity(old_point_entity).despawn();
-        *mode = PlanMode::Select(new_point_entity);
+    if let Action::Merge(old_id, new_id) = **action {
+        document.merge_points(old_id, new_id);
+        *mode = PlanMode::Select(new_id);
     }
 }
 
+// ending a move of the tracked point takes the other selected points along
 fn handle_move_action(
     action: Res<CurrentAction>,
-    mut query: Query<&mut Transform, With<Point>>,
+    selection: Res<Selection>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Move(id, position) = **action {
+        let tracked = matches!(*mode, PlanMode::Track(tracked, _) if tracked == id);
+        let offset = position - document.position(id).unwrap_or(position);
+        for other in selection
+            .points
+            .iter()
+            .filter(|other| tracked && **other != id)
+        {
+            if let Some(other_position) = document.position(*other) {
+                document.move_point(*other, other_position + offset);
+            }
+        }
+        document.move_point(id, position);
+        *mode = PlanMode::Select(id);
+    }
+}
+
+fn handle_paste_action(
+    action: Res<CurrentAction>,
+    mut clipboard: NonSendMut<Clipboard>,
+    mut document: ResMut<PlanDocument>,
+    mut selection: ResMut<Selection>,
     mut mode: ResMut<PlanMode>,
+    mut notifications: EventWriter<Notification>,
 ) {
-    if let Action::Move(entity, position) = **action {
-        let mut transform = query.get_mut(entity).unwrap();
-        transform.translation.x = position.x;
-        transform.translation.y = position.y;
-        *mode = PlanMode::Select(entity);
+    if let Action::Paste(position) = **action {
+        match clipboard.read_plan() {
+            Some(fragment) => {
+                place_fragment(
+                    &fragment,
+                    position,
+                    &mut document,
+                    &mut selection,
+                    &mut mode,
+                );
+            }
+            None => notifications.send(Notification::warning(
+                "The clipboard does not hold any part of a plan",
+            )),
+        }
+    }
+}
+
+fn handle_redo_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut history: ResMut<History>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Redo = **action {
+        if let Some(next) = history.redo.pop() {
+            history.undo.push(document.clone());
+            document.replace(next);
+            *mode = PlanMode::Default;
+        }
+    }
+}
+
+fn handle_repair_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut notifications: EventWriter<Notification>,
+) {
+    if let Action::Repair = **action {
+        let repairs = repair::repair(&mut document);
+        for repair in &repairs {
+            notifications.send(Notification::info(format!("Repair: {repair}")));
+        }
+        notifications.send(Notification::info(match repairs.len() {
+            0 => "Repair: nothing to change".to_string(),
+            count => format!("Repair: {count} changes"),
+        }));
+    }
+}
+
+// cancels a move or transformation
+fn handle_restore_action(
+    action: Res<CurrentAction>,
+    origins: Res<TrackOrigins>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Restore(id) = **action {
+        if let PlanMode::Track(_, TrackMode::Move(_) | TrackMode::Transform(..)) = *mode {
+            restore_origins(&origins, &mut document);
+            *mode = PlanMode::Select(id);
+        }
     }
 }
 
 fn handle_select_action(action: Res<CurrentAction>, mut mode: ResMut<PlanMode>) {
-    if let Action::Select(entity) = **action {
-        *mode = PlanMode::Select(entity);
+    if let Action::Select(id) = **action {
+        *mode = PlanMode::Select(id);
+    }
+}
+
+fn handle_select_all_action(
+    action: Res<CurrentAction>,
+    document: Res<PlanDocument>,
+    mut selection: ResMut<Selection>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::SelectAll = **action {
+        selection.points = document.points().map(|(id, _)| id).collect();
+        *mode = match selection.points.last() {
+            Some(id) => PlanMode::Select(*id),
+            None => PlanMode::Default,
+        };
+    }
+}
+
+// the new point lands on the line, nearest to the position
+fn handle_split_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Split(id, position) = **action {
+        let Some((a, b)) = document.line_positions(id) else {
+            return;
+        };
+        let direction = b - a;
+        let t = match direction.length_squared() {
+            length if length > 0.0 => ((position - a).dot(direction) / length).clamp(0.0, 1.0),
+            _ => 0.0,
+        };
+        if let Some(new_id) = document.split_line(id, a + direction * t) {
+            *mode = PlanMode::Select(new_id);
+        }
+    }
+}
+
+// a new point with a second one connected to it and placed by the cursor
+fn handle_start_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Start(position) = **action {
+        let start_id = document.add_point(position);
+        if let Some((end_id, _)) = document.extend(start_id, position) {
+            *mode = PlanMode::Track(end_id, TrackMode::Place);
+        }
     }
 }
 
+fn handle_toggle_select_action(
+    action: Res<CurrentAction>,
+    mut selection: ResMut<Selection>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::ToggleSelect(id) = **action {
+        match selection.contains(id) {
+            true => selection.points.retain(|other| *other != id),
+            false => selection.points.push(id),
+        }
+        *mode = match selection.points.last() {
+            Some(last) => PlanMode::Select(*last),
+            None => PlanMode::Default,
+        };
+    }
+}
+
+// a point outside the selection is picked up on its own
 fn handle_track_action(
     action: Res<CurrentAction>,
-    query: Query<&Transform, With<Point>>,
+    document: Res<PlanDocument>,
+    selection: Res<Selection>,
+    mut origins: ResMut<TrackOrigins>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Track(id) = **action {
+        if let Some(position) = document.position(id) {
+            match selection.contains(id) {
+                true => origins.record(&document, &selection.points),
+                false => origins.record(&document, &[id]),
+            }
+            *mode = PlanMode::Track(id, TrackMode::Move(position))
+        }
+    }
+}
+
+fn handle_track_transform_action(
+    action: Res<CurrentAction>,
+    pivot: Res<Pivot>,
+    document: Res<PlanDocument>,
+    selection: Res<Selection>,
+    mut origins: ResMut<TrackOrigins>,
     mut mode: ResMut<PlanMode>,
 ) {
-    if let Action::Track(entity) = **action {
-        let transform = query.get(entity).unwrap();
-        let position = transform.translation.truncate();
-        *mode = PlanMode::Track(entity, TrackMode::Move(position))
+    if let Action::TrackTransform(kind, start) = **action {
+        let Some(id) = mode.selection() else {
+            return;
+        };
+        let Some(pivot) = pivot.position(&document, &selection, id, Some(start)) else {
+            return;
+        };
+        let center = transform::center(&document, &selection.points);
+        let start = transform::reference(pivot, [Some(start), center].into_iter().flatten());
+        origins.record(&document, &selection.points);
+        *mode = PlanMode::Track(id, TrackMode::Transform(kind, pivot, start));
+    }
+}
+
+// a typed transformation replaces the one being tracked
+fn handle_transform_action(
+    action: Res<CurrentAction>,
+    origins: Res<TrackOrigins>,
+    selection: Res<Selection>,
+    mut document: ResMut<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Transform(transformation) = **action {
+        if let PlanMode::Track(_, TrackMode::Transform(..)) = *mode {
+            restore_origins(&origins, &mut document);
+        }
+        for point in &selection.points {
+            if let Some(position) = document.position(*point) {
+                document.move_point(*point, transformation.apply(position));
+            }
+        }
+        if let Some(id) = mode.selection() {
+            *mode = PlanMode::Select(id);
+        }
+    }
+}
+
+fn handle_undo_action(
+    action: Res<CurrentAction>,
+    mut document: ResMut<PlanDocument>,
+    mut history: ResMut<History>,
+    mut mode: ResMut<PlanMode>,
+) {
+    if let Action::Undo = **action {
+        if let Some(previous) = history.undo.pop() {
+            history.redo.push(document.clone());
+            document.replace(previous);
+            *mode = PlanMode::Default;
+        }
     }
 }
 
@@ -187,3 +849,95 @@ fn handle_unselect_action(action: Res<CurrentAction>, mut mode: ResMut<PlanMode>
         *mode = PlanMode::Default;
     }
 }
+
+fn sync_selection(
+    mode: Res<PlanMode>,
+    document: Res<PlanDocument>,
+    mut selection: ResMut<Selection>,
+) {
+    selection.sync(&mode, &document);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::spatial::SpatialIndex;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugin(ActionPlugin)
+            .add_event::<Notification>()
+            .insert_non_send_resource(Clipboard::new())
+            .init_resource::<PlanDocument>()
+            .init_resource::<SpatialIndex>()
+            .init_resource::<PlanMode>()
+            .init_resource::<Selection>()
+            .init_resource::<Pivot>()
+            .init_resource::<TrackOrigins>();
+        app
+    }
+
+    fn run(app: &mut App, action: Action) {
+        app.world.resource_mut::<ActionQueue>().push_back(action);
+        app.update();
+    }
+
+    #[test]
+    fn cancelling_the_first_wall_removes_its_start() {
+        let mut app = app();
+        run(&mut app, Action::Start(Vec2::ZERO));
+        let PlanMode::Track(end, TrackMode::Place) = *app.world.resource::<PlanMode>() else {
+            panic!("the end of the wall is not tracked");
+        };
+        run(&mut app, Action::Discard(end));
+        assert_eq!(app.world.resource::<PlanDocument>().points().count(), 0);
+        assert!(app.world.resource::<History>().undo.is_empty());
+
+        // a finished wall and a point that was there before are kept
+        run(&mut app, Action::Start(Vec2::ZERO));
+        let PlanMode::Track(end, _) = *app.world.resource::<PlanMode>() else {
+            panic!("the end of the wall is not tracked");
+        };
+        run(&mut app, Action::Move(end, Vec2::X));
+        run(&mut app, Action::Extend(end));
+        let PlanMode::Track(next, _) = *app.world.resource::<PlanMode>() else {
+            panic!("the next wall is not tracked");
+        };
+        run(&mut app, Action::Discard(next));
+        let document = app.world.resource::<PlanDocument>();
+        assert_eq!(document.points().count(), 2);
+        assert_eq!(document.lines().count(), 1);
+    }
+
+    #[test]
+    fn undo_waits_for_the_tracked_edit() {
+        let mut history = History::default();
+        let mut document = PlanDocument::default();
+        let start = document.add_point(Vec2::ZERO);
+        history.update(&document, &PlanMode::Default, PlanDocument::default(), None);
+
+        // a wall is started and its end point picked up
+        let before = document.clone();
+        let (end, _) = document.extend(start, Vec2::X).unwrap();
+        let tracking = PlanMode::Track(end, TrackMode::Place);
+        history.update(&document, &tracking, before, None);
+        assert_eq!(history.undo.len(), 1);
+        assert!(history.pending.is_some());
+
+        let undo = Action::Undo.validate(&document, &tracking, &history);
+        assert!(matches!(undo, Err(ActionError::Tracking)));
+        assert!(history.pending.is_some());
+        assert!(history.redo.is_empty());
+
+        // dropping the point records the wall as a single step
+        let before = document.clone();
+        document.move_point(end, Vec2::new(2.0, 0.0));
+        let pending = history.pending.take();
+        history.update(&document, &PlanMode::Select(end), before, pending);
+        assert_eq!(history.undo.len(), 2);
+        assert!(history.pending.is_none());
+        assert_eq!(history.undo[1].points().count(), 1);
+        let undo = Action::Undo.validate(&document, &PlanMode::Select(end), &history);
+        assert!(undo.is_ok());
+    }
+}
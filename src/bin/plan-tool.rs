@@ -0,0 +1,285 @@
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use layer_home_planner::{
+    analysis, document::PlanDocument, export, integrity, repair, Unit, UnitSystem,
+};
+
+const USAGE: &str = "\
+usage:
+  plan-tool convert <input> <output> [--format plan|svg|csv] [--unit m|cm|mm|ft-in]
+  plan-tool report <input> [--unit m|cm|mm|ft-in]
+  plan-tool check <input>
+  plan-tool repair <input> <output>
+
+`-` reads from standard input or writes to standard output, the output
+format defaults to the extension of the output path";
+
+const EXIT_INVALID: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+
+enum Format {
+    Plan,
+    Svg,
+    Csv,
+}
+
+impl Format {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "plan" | "txt" => Some(Format::Plan),
+            "svg" => Some(Format::Svg),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "-" => Some(Format::Plan),
+            _ => Format::parse(Path::new(path).extension()?.to_str()?),
+        }
+    }
+
+    fn write(&self, document: &PlanDocument, unit: Unit) -> String {
+        match self {
+            Format::Plan => document.to_string(),
+            Format::Svg => export::to_svg(document, unit),
+            Format::Csv => export::to_csv(document, unit),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    Usage(String),
+    Invalid(String),
+}
+
+impl Error {
+    fn code(&self) -> u8 {
+        match self {
+            Error::Usage(_) => EXIT_USAGE,
+            Error::Invalid(_) => EXIT_INVALID,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            match &error {
+                Error::Usage(message) => eprintln!("error: {message}\n\n{USAGE}"),
+                Error::Invalid(message) => eprintln!("error: {message}"),
+            }
+            ExitCode::from(error.code())
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Error> {
+    let (command, args) = args
+        .split_first()
+        .ok_or_else(|| Error::Usage("missing command".into()))?;
+    let arguments = Arguments::parse(args)?;
+    match (command.as_str(), &arguments.paths[..]) {
+        ("convert", [input, output]) => {
+            let format = match arguments.option("format") {
+                Some(name) => Format::parse(name),
+                None => Format::from_path(output),
+            }
+            .ok_or_else(|| Error::Usage(format!("unknown output format for `{output}`")))?;
+            let units = arguments.units()?;
+            let document = read(input)?;
+            write(output, &format.write(&document, units.unit))
+        }
+        ("report", [input]) => {
+            let units = arguments.units()?;
+            let document = read(input)?;
+            print!("{}", report(&document, &units));
+            Ok(())
+        }
+        ("check", [input]) => {
+            let document = read(input)?;
+            let issues = integrity::check(&document);
+            for issue in &issues {
+                println!("{input}: {issue}");
+            }
+            match issues.len() {
+                0 => Ok(()),
+                count => Err(Error::Invalid(format!("{input}: {count} issues found"))),
+            }
+        }
+        ("repair", [input, output]) => {
+            let mut document = read(input)?;
+            for repair in repair::repair(&mut document) {
+                eprintln!("{input}: {repair}");
+            }
+            write(output, &document.to_string())
+        }
+        ("convert" | "report" | "check" | "repair", _) => Err(Error::Usage(format!(
+            "wrong number of paths for `{command}`"
+        ))),
+        _ => Err(Error::Usage(format!("unknown command `{command}`"))),
+    }
+}
+
+// positional paths and `--name value` options
+struct Arguments<'a> {
+    paths: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Arguments<'a> {
+    fn parse(args: &'a [String]) -> Result<Self, Error> {
+        let mut arguments = Self {
+            paths: Vec::new(),
+            options: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| Error::Usage(format!("missing value for `{arg}`")))?;
+                    arguments.options.push((name, value));
+                }
+                None => arguments.paths.push(arg),
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    }
+
+    fn units(&self) -> Result<UnitSystem, Error> {
+        let mut units = UnitSystem::default();
+        if let Some(symbol) = self.option("unit") {
+            let unit = Unit::parse(symbol)
+                .ok_or_else(|| Error::Usage(format!("unknown unit `{symbol}`")))?;
+            units.set_unit(unit);
+        }
+        Ok(units)
+    }
+}
+
+fn read(path: &str) -> Result<PlanDocument, Error> {
+    let mut source = String::new();
+    let result = match path {
+        "-" => io::stdin().read_to_string(&mut source).map(|_| ()),
+        _ => fs::read_to_string(path).map(|text| source = text),
+    };
+    result.map_err(|error| Error::Invalid(format!("could not read `{path}`: {error}")))?;
+    source
+        .parse()
+        .map_err(|error| Error::Invalid(format!("{path}: {error}")))
+}
+
+fn write(path: &str, contents: &str) -> Result<(), Error> {
+    let result = match path {
+        "-" => io::stdout().write_all(contents.as_bytes()),
+        _ => fs::write(path, contents),
+    };
+    result.map_err(|error| Error::Invalid(format!("could not write `{path}`: {error}")))
+}
+
+fn report(document: &PlanDocument, units: &UnitSystem) -> String {
+    let mut report = format!(
+        "walls: {}\ntotal wall length: {}\n",
+        document.lines().count(),
+        units.format(analysis::wall_length(document)),
+    );
+    let rooms = analysis::rooms(document);
+    report += &format!(
+        "rooms: {}\ntotal room area: {}\n",
+        rooms.len(),
+        units.format_area(rooms.iter().map(|room| room.area).sum()),
+    );
+    for (index, room) in rooms.iter().enumerate() {
+        let points: Vec<String> = room.points.iter().map(ToString::to_string).collect();
+        report += &format!(
+            "  room {}: {} (points {})\n",
+            index + 1,
+            units.format_area(room.area),
+            points.join(", "),
+        );
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // a broken plan with two points on top of each other
+    const BROKEN: &str =
+        "point 1 0 0\npoint 2 0 0\npoint 3 2 0\npoint 4 0 2\nline 5 1 3\nline 6 2 4\n";
+    const VALID: &str = "point 1 0 0\npoint 2 2 0\nline 3 1 2\n";
+
+    fn path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("plan-tool-{}-{name}", std::process::id()))
+    }
+
+    fn file(name: &str, contents: &str) -> String {
+        let path = path(name);
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn code(args: &[&str]) -> u8 {
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        run(&args).map_or_else(|error| error.code(), |()| 0)
+    }
+
+    #[test]
+    fn check_fails_on_issues() {
+        let valid = file("check-valid.plan", VALID);
+        let broken = file("check-broken.plan", BROKEN);
+        let unparsable = file("check-unparsable.plan", "point 1 x 0\n");
+        assert_eq!(code(&["check", &valid]), 0);
+        assert_eq!(code(&["check", &broken]), EXIT_INVALID);
+        assert_eq!(code(&["check", &unparsable]), EXIT_INVALID);
+        let missing = path("check-missing.plan");
+        assert_eq!(code(&["check", &missing.to_string_lossy()]), EXIT_INVALID);
+        assert_eq!(code(&["check"]), EXIT_USAGE);
+        assert_eq!(code(&["check", &valid, &broken]), EXIT_USAGE);
+        assert_eq!(code(&["verify", &valid]), EXIT_USAGE);
+        assert_eq!(code(&[]), EXIT_USAGE);
+        for path in [valid, broken, unparsable] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn repair_writes_a_plan_that_checks() {
+        let broken = file("repair-broken.plan", BROKEN);
+        let output = path("repair-output.plan");
+        let output = output.to_string_lossy();
+        assert_eq!(code(&["repair", &broken, &output]), 0);
+        assert_eq!(code(&["check", &output]), 0);
+        let repaired: PlanDocument = fs::read_to_string(&*output).unwrap().parse().unwrap();
+        assert_eq!(repaired.points().count(), 3);
+        let unparsable = file("repair-unparsable.plan", "line 1 2 3\n");
+        assert_eq!(code(&["repair", &unparsable, &output]), EXIT_INVALID);
+        assert_eq!(code(&["repair", &broken]), EXIT_USAGE);
+        assert_eq!(code(&["repair", &broken, &output, "--unit"]), EXIT_USAGE);
+        for path in [&broken, &*output, &unparsable] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
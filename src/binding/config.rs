@@ -0,0 +1,678 @@
+use std::{fmt, fs};
+
+use bevy::prelude::*;
+
+use crate::{plan::PlanMode, tool::Tool};
+
+use super::{best_match, Command};
+
+pub const BINDINGS_PATH: &str = "assets/config/bindings.cfg";
+const DEFAULT_BINDINGS: &str = include_str!("../../assets/config/bindings.cfg");
+
+const KEYS: [KeyCode; 86] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Escape,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Apostrophe,
+    KeyCode::Backslash,
+    KeyCode::Colon,
+    KeyCode::Comma,
+    KeyCode::Equals,
+    KeyCode::Grave,
+    KeyCode::LBracket,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::RBracket,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+];
+
+const MOUSE_BUTTONS: [(MouseButton, &str); 3] = [
+    (MouseButton::Left, "MouseLeft"),
+    (MouseButton::Right, "MouseRight"),
+    (MouseButton::Middle, "MouseMiddle"),
+];
+
+const MODIFIER_KEYS: [KeyCode; 6] = [
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Button {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Button {
+    fn parse(name: &str) -> Option<Self> {
+        let key = KEYS
+            .into_iter()
+            .find(|key| format!("{key:?}").eq_ignore_ascii_case(name))
+            .map(Button::Key);
+        let mouse_button = MOUSE_BUTTONS
+            .into_iter()
+            .find(|(_, button_name)| button_name.eq_ignore_ascii_case(name))
+            .map(|(button, _)| Button::Mouse(button));
+        key.or(mouse_button)
+    }
+
+    pub fn just_pressed(
+        keyboard_input: &Input<KeyCode>,
+        mouse_input: &Input<MouseButton>,
+    ) -> Vec<Self> {
+        let keys = keyboard_input
+            .get_just_pressed()
+            .filter(|key| !MODIFIER_KEYS.contains(key))
+            .map(|key| Button::Key(*key));
+        let mouse_buttons = mouse_input
+            .get_just_pressed()
+            .map(|button| Button::Mouse(*button));
+        keys.chain(mouse_buttons).collect()
+    }
+
+    fn pressed(&self, keyboard_input: &Input<KeyCode>, mouse_input: &Input<MouseButton>) -> bool {
+        match self {
+            Button::Key(key) => keyboard_input.pressed(*key),
+            Button::Mouse(button) => mouse_input.pressed(*button),
+        }
+    }
+}
+
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Button::Key(key) => write!(f, "{key:?}"),
+            Button::Mouse(button) => match MOUSE_BUTTONS.iter().find(|(b, _)| b == button) {
+                Some((_, name)) => write!(f, "{name}"),
+                None => write!(f, "{button:?}"),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub fn pressed(keyboard_input: &Input<KeyCode>) -> Self {
+        Self {
+            ctrl: keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl]),
+            shift: keyboard_input.any_pressed([KeyCode::LShift, KeyCode::RShift]),
+            alt: keyboard_input.any_pressed([KeyCode::LAlt, KeyCode::RAlt]),
+        }
+    }
+
+    fn contains(&self, other: Modifiers) -> bool {
+        (self.ctrl || !other.ctrl) && (self.shift || !other.shift) && (self.alt || !other.alt)
+    }
+
+    pub fn count(&self) -> usize {
+        [self.ctrl, self.shift, self.alt]
+            .into_iter()
+            .filter(|pressed| *pressed)
+            .count()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Combo {
+    pub modifiers: Modifiers,
+    pub button: Button,
+}
+
+impl Combo {
+    pub fn new(modifiers: Modifiers, button: Button) -> Self {
+        Self { modifiers, button }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let button_name = parts.pop().unwrap_or_default();
+        let button = Button::parse(button_name)
+            .ok_or_else(|| format!("unknown key or button `{button_name}`"))?;
+        let mut modifiers = Modifiers::default();
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "ctrl" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                _ => return Err(format!("unknown modifier `{part}`")),
+            }
+        }
+        Ok(Self { modifiers, button })
+    }
+
+    // extra modifiers are allowed so that Alt (fine snapping) can be held while clicking
+    pub fn matches(&self, pressed: Combo) -> bool {
+        self.button == pressed.button && pressed.modifiers.contains(self.modifiers)
+    }
+}
+
+impl fmt::Display for Combo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{}", self.button)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Sequence {
+    pub first: Combo,
+    pub second: Option<Combo>,
+}
+
+impl Sequence {
+    fn parse(text: &str) -> Result<Self, String> {
+        let combos = text
+            .split_whitespace()
+            .map(Combo::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        match combos[..] {
+            [first] => Ok(Self {
+                first,
+                second: None,
+            }),
+            [first, second] => Ok(Self {
+                first,
+                second: Some(second),
+            }),
+            _ => Err(format!("`{text}` must be one input or a two-input chord")),
+        }
+    }
+
+    pub fn is_chord(&self) -> bool {
+        self.second.is_some()
+    }
+}
+
+impl fmt::Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.second {
+            Some(second) => write!(f, "{} {}", self.first, second),
+            None => write!(f, "{}", self.first),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BindingContext {
+    Global,
+    Default,
+    Select,
+    Track,
+    Wall,
+    Measure,
+    Room,
+    Door,
+    Text,
+}
+
+impl BindingContext {
+    const MODES: [BindingContext; 8] = [
+        BindingContext::Default,
+        BindingContext::Select,
+        BindingContext::Track,
+        BindingContext::Wall,
+        BindingContext::Measure,
+        BindingContext::Room,
+        BindingContext::Door,
+        BindingContext::Text,
+    ];
+
+    // the select tool has a table per mode, the other tools one each
+    pub fn from_state(tool: Tool, mode: &PlanMode) -> Self {
+        match (tool, mode) {
+            (Tool::Select, PlanMode::Default) => BindingContext::Default,
+            (Tool::Select, PlanMode::Select(_)) => BindingContext::Select,
+            (Tool::Select, PlanMode::Track(_, _)) => BindingContext::Track,
+            (Tool::Wall, _) => BindingContext::Wall,
+            (Tool::Measure, _) => BindingContext::Measure,
+            (Tool::Room, _) => BindingContext::Room,
+            (Tool::Door, _) => BindingContext::Door,
+            (Tool::Text, _) => BindingContext::Text,
+        }
+    }
+
+    pub fn tool(&self) -> Option<Tool> {
+        match self {
+            BindingContext::Global => None,
+            BindingContext::Default | BindingContext::Select | BindingContext::Track => {
+                Some(Tool::Select)
+            }
+            BindingContext::Wall => Some(Tool::Wall),
+            BindingContext::Measure => Some(Tool::Measure),
+            BindingContext::Room => Some(Tool::Room),
+            BindingContext::Door => Some(Tool::Door),
+            BindingContext::Text => Some(Tool::Text),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BindingContext::Global => "global",
+            BindingContext::Default => "default",
+            BindingContext::Select => "select",
+            BindingContext::Track => "track",
+            BindingContext::Wall => "wall",
+            BindingContext::Measure => "measure",
+            BindingContext::Room => "room",
+            BindingContext::Door => "door",
+            BindingContext::Text => "text",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        [BindingContext::Global]
+            .into_iter()
+            .chain(Self::MODES)
+            .find(|context| context.name() == name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Binding {
+    pub context: BindingContext,
+    pub sequence: Sequence,
+    pub command: Command,
+}
+
+#[derive(Resource)]
+pub struct Bindings {
+    bindings: Vec<Binding>,
+}
+
+impl FromWorld for Bindings {
+    fn from_world(_: &mut World) -> Self {
+        let (bindings, warnings) = Self::load();
+        for warning in warnings {
+            warn!("{warning}");
+        }
+        bindings
+    }
+}
+
+impl Bindings {
+    // the warnings describe unreadable, invalid and conflicting bindings
+    pub fn load() -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let source = fs::read_to_string(BINDINGS_PATH).unwrap_or_else(|error| {
+            warnings.push(format!(
+                "Using default bindings, could not read {BINDINGS_PATH}: {error}"
+            ));
+            DEFAULT_BINDINGS.to_string()
+        });
+        let (bindings, errors) = Self::parse(&source);
+        for error in errors {
+            warnings.push(format!("Invalid binding in {BINDINGS_PATH}, {error}"));
+        }
+        for conflict in bindings.conflicts() {
+            warnings.push(format!("Conflicting bindings, {conflict}"));
+        }
+        (bindings, warnings)
+    }
+
+    pub fn parse(source: &str) -> (Self, Vec<String>) {
+        let mut bindings = Vec::new();
+        let mut errors = Vec::new();
+        let mut context = BindingContext::Global;
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let result =
+                if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                    BindingContext::parse(name.trim())
+                        .map(|new_context| context = new_context)
+                        .ok_or_else(|| format!("unknown mode `{name}`"))
+                } else {
+                    Self::parse_binding(line, context).map(|binding| bindings.push(binding))
+                };
+            if let Err(error) = result {
+                errors.push(format!("line {}: {error}", index + 1));
+            }
+        }
+        (Self { bindings }, errors)
+    }
+
+    fn parse_binding(line: &str, context: BindingContext) -> Result<Binding, String> {
+        let (sequence, command) = line
+            .split_once('=')
+            .ok_or_else(|| "expected `input = command`".to_string())?;
+        let command = Command::parse(command.trim())
+            .ok_or_else(|| format!("unknown command `{}`", command.trim()))?;
+        Ok(Binding {
+            context,
+            sequence: Sequence::parse(sequence)?,
+            command,
+        })
+    }
+
+    // mode bindings come before global ones so they take precedence
+    pub fn active(&self, context: BindingContext) -> impl Iterator<Item = &Binding> {
+        let mode_bindings = self.bindings.iter().filter(move |b| b.context == context);
+        let global_bindings = self
+            .bindings
+            .iter()
+            .filter(move |b| b.context == BindingContext::Global && context != b.context);
+        mode_bindings.chain(global_bindings)
+    }
+
+    // for commands that last as long as their inputs are held down, the inputs
+    // of a chord all have to be held
+    pub fn held(
+        &self,
+        context: BindingContext,
+        command: Command,
+        keyboard_input: &Input<KeyCode>,
+        mouse_input: &Input<MouseButton>,
+    ) -> bool {
+        let modifiers = Modifiers::pressed(keyboard_input);
+        let held = |combo: Combo| {
+            combo.button.pressed(keyboard_input, mouse_input)
+                && combo.matches(Combo::new(modifiers, combo.button))
+        };
+        self.active(context)
+            .filter(|binding| binding.command == command)
+            .any(|binding| {
+                held(binding.sequence.first) && binding.sequence.second.into_iter().all(held)
+            })
+    }
+
+    // presses are resolved the way `PendingChord` does, so a binding also
+    // answers presses with more modifiers than it names
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for context in BindingContext::MODES {
+            // a modified input bound in one mode of a tool should not quietly
+            // do something else in its other modes
+            let modified: Vec<Combo> = self
+                .bindings
+                .iter()
+                .filter(|binding| binding.context.tool() == context.tool())
+                .map(|binding| binding.sequence.first)
+                .filter(|combo| combo.modifiers.count() > 0)
+                .collect();
+            let active: Vec<&Binding> = self.active(context).collect();
+            let singles = || active.iter().filter(|b| !b.sequence.is_chord());
+            let resolve = |pressed| best_match(singles(), |b| Some(b.sequence.first), pressed);
+            let mut found = Vec::new();
+            for (index, a) in active.iter().enumerate() {
+                for b in &active[index + 1..] {
+                    // a mode binding overriding a global one is intended
+                    if a.sequence == b.sequence && a.context == b.context {
+                        found.push(format!(
+                            "`{}` is bound to both `{}` and `{}` in {} mode",
+                            a.sequence,
+                            a.command.name(),
+                            b.command.name(),
+                            a.context.name(),
+                        ));
+                    }
+                }
+            }
+            for chord in active.iter().filter(|b| b.sequence.is_chord()) {
+                if let Some(single) = resolve(chord.sequence.first) {
+                    found.push(format!(
+                        "`{}` prevents the chord `{}` in {} mode",
+                        single.sequence,
+                        chord.sequence,
+                        context.name(),
+                    ));
+                }
+            }
+            // prevented chords are reported above
+            let chords: Vec<Combo> = active
+                .iter()
+                .filter(|b| b.sequence.is_chord())
+                .map(|b| b.sequence.first)
+                .collect();
+            for pressed in modified.iter().filter(|combo| !chords.contains(combo)) {
+                let Some(binding) = resolve(*pressed) else {
+                    continue;
+                };
+                if binding.sequence.first != *pressed {
+                    found.push(format!(
+                        "`{pressed}` falls through to `{}` ({}) in {} mode",
+                        binding.sequence,
+                        binding.command.name(),
+                        context.name(),
+                    ));
+                }
+            }
+            for conflict in found {
+                if !conflicts.contains(&conflict) {
+                    conflicts.push(conflict);
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combo(text: &str) -> Combo {
+        Combo::parse(text).unwrap()
+    }
+
+    #[test]
+    fn default_bindings_are_valid() {
+        let (bindings, errors) = Bindings::parse(DEFAULT_BINDINGS);
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(bindings.conflicts(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn combos_and_sequences() {
+        let parsed = combo("ctrl + Shift+z");
+        assert!(parsed.modifiers.ctrl && parsed.modifiers.shift && !parsed.modifiers.alt);
+        assert_eq!(parsed.button, Button::Key(KeyCode::Z));
+        assert_eq!(
+            combo("Alt+mouseleft").button,
+            Button::Mouse(MouseButton::Left)
+        );
+        assert_eq!(
+            Combo::parse("Meta+Z").unwrap_err(),
+            "unknown modifier `Meta`"
+        );
+        assert_eq!(
+            Combo::parse("Ctrl+").unwrap_err(),
+            "unknown key or button ``"
+        );
+        let chord = Sequence::parse("G  Shift+X").unwrap();
+        assert!(chord.is_chord());
+        assert_eq!(chord.to_string(), "G Shift+X");
+        assert!(Sequence::parse("G X Y").is_err());
+    }
+
+    #[test]
+    fn extra_modifiers_still_match() {
+        let binding = combo("MouseLeft");
+        assert!(binding.matches(combo("Alt+MouseLeft")));
+        assert!(!combo("Ctrl+MouseLeft").matches(binding));
+        assert!(!binding.matches(combo("MouseRight")));
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let source = "# comment\n\n[nowhere]\nA = fly\nA delete\nA = delete # trailing\n";
+        let (bindings, errors) = Bindings::parse(source);
+        assert_eq!(
+            errors,
+            [
+                "line 3: unknown mode `nowhere`",
+                "line 4: unknown command `fly`",
+                "line 5: expected `input = command`",
+            ]
+        );
+        assert_eq!(bindings.bindings.len(), 1);
+        assert_eq!(bindings.bindings[0].command, Command::Delete);
+    }
+
+    #[test]
+    fn mode_bindings_come_first() {
+        let source = "[global]\nEscape = unselect\n[track]\nEscape = cancel\n";
+        let (bindings, _) = Bindings::parse(source);
+        let commands = |context| {
+            bindings
+                .active(context)
+                .map(|binding| binding.command)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            commands(BindingContext::Track),
+            [Command::Cancel, Command::Unselect]
+        );
+        assert_eq!(commands(BindingContext::Wall), [Command::Unselect]);
+        assert!(bindings.conflicts().is_empty());
+    }
+
+    #[test]
+    fn conflicts_are_reported() {
+        let source = "[global]\nG = delete\n[wall]\nD = delete\nD = cancel\nG X = cancel\n";
+        let (bindings, _) = Bindings::parse(source);
+        assert_eq!(
+            bindings.conflicts(),
+            [
+                "`D` is bound to both `delete` and `cancel` in wall mode",
+                "`G` prevents the chord `G X` in wall mode",
+            ]
+        );
+    }
+
+    #[test]
+    fn held_commands_need_every_input() {
+        let (bindings, _) = Bindings::parse("MouseMiddle = pan\nSpace MouseLeft = pan\n");
+        let mut keyboard_input = Input::<KeyCode>::default();
+        let mut mouse_input = Input::<MouseButton>::default();
+        let held = |keyboard_input: &Input<KeyCode>, mouse_input: &Input<MouseButton>| {
+            bindings.held(
+                BindingContext::Wall,
+                Command::Pan,
+                keyboard_input,
+                mouse_input,
+            )
+        };
+        mouse_input.press(MouseButton::Left);
+        assert!(!held(&keyboard_input, &mouse_input));
+        keyboard_input.press(KeyCode::Space);
+        assert!(held(&keyboard_input, &mouse_input));
+        mouse_input.release(MouseButton::Left);
+        keyboard_input.press(KeyCode::LShift);
+        mouse_input.press(MouseButton::Middle);
+        assert!(held(&keyboard_input, &mouse_input));
+        assert!(!bindings.held(
+            BindingContext::Wall,
+            Command::Console,
+            &keyboard_input,
+            &mouse_input
+        ));
+    }
+
+    #[test]
+    fn conflicts_follow_extra_modifiers() {
+        let source = "[global]\nG = delete\nR = room-tool\n[select]\nShift+G X = cancel\nShift+R = rotate\n[wall]\nShift+R = cancel\n";
+        let (bindings, _) = Bindings::parse(source);
+        assert_eq!(
+            bindings.conflicts(),
+            [
+                "`Shift+G` falls through to `G` (delete) in default mode",
+                "`Shift+R` falls through to `R` (room-tool) in default mode",
+                "`G` prevents the chord `Shift+G X` in select mode",
+                "`Shift+G` falls through to `G` (delete) in track mode",
+                "`Shift+R` falls through to `R` (room-tool) in track mode",
+            ]
+        );
+    }
+}
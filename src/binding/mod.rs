@@ -0,0 +1,331 @@
+pub mod config;
+
+use std::cmp::Reverse;
+
+use bevy::prelude::*;
+
+use crate::{
+    action::{Action, ActionQueue},
+    camera::CameraPan,
+    input::{Cursor, Hover, InputFocus},
+    notification::Notification,
+    plan::PlanMode,
+    tool::Tool,
+    units::UnitSystem,
+    AppSet,
+};
+
+use self::config::{Binding, BindingContext, Bindings, Button, Combo, Modifiers};
+
+const CHORD_TIMEOUT: f32 = 1.0;
+
+pub struct BindingPlugin;
+
+impl Plugin for BindingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bindings>()
+            .add_event::<Command>()
+            .add_systems(
+                (process_bindings, reload_bindings)
+                    .chain()
+                    .in_set(AppSet::Binding),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Command {
+    Create,
+    Extend,
+    Track,
+    Delete,
+    Unselect,
+    Click,
+    Cancel,
+    FrameAll,
+    FrameSelection,
+    CycleUnit,
+    Calibrate,
+    IncreaseOpacity,
+    DecreaseOpacity,
+    ReloadBindings,
+    Save,
+    Open,
+    ToggleIssues,
+    Repair,
+    Undo,
+    Redo,
+    ToggleLog,
+    ScrollLogUp,
+    ScrollLogDown,
+    CycleTheme,
+    ReloadTheme,
+    SelectTool,
+    WallTool,
+    MeasureTool,
+    RoomTool,
+    DoorTool,
+    TextTool,
+    Split,
+    Dissolve,
+    Properties,
+    ContextMenu,
+    Copy,
+    Cut,
+    Paste,
+    Duplicate,
+    SelectAll,
+    ToggleSelection,
+    Rotate,
+    Scale,
+    Stretch,
+    Mirror,
+    CyclePivot,
+    Pan,
+    Console,
+}
+
+impl Command {
+    pub const ALL: [Command; 48] = [
+        Command::Create,
+        Command::Extend,
+        Command::Track,
+        Command::Delete,
+        Command::Unselect,
+        Command::Click,
+        Command::Cancel,
+        Command::FrameAll,
+        Command::FrameSelection,
+        Command::CycleUnit,
+        Command::Calibrate,
+        Command::IncreaseOpacity,
+        Command::DecreaseOpacity,
+        Command::ReloadBindings,
+        Command::Save,
+        Command::Open,
+        Command::ToggleIssues,
+        Command::Repair,
+        Command::Undo,
+        Command::Redo,
+        Command::ToggleLog,
+        Command::ScrollLogUp,
+        Command::ScrollLogDown,
+        Command::CycleTheme,
+        Command::ReloadTheme,
+        Command::SelectTool,
+        Command::WallTool,
+        Command::MeasureTool,
+        Command::RoomTool,
+        Command::DoorTool,
+        Command::TextTool,
+        Command::Split,
+        Command::Dissolve,
+        Command::Properties,
+        Command::ContextMenu,
+        Command::Copy,
+        Command::Cut,
+        Command::Paste,
+        Command::Duplicate,
+        Command::SelectAll,
+        Command::ToggleSelection,
+        Command::Rotate,
+        Command::Scale,
+        Command::Stretch,
+        Command::Mirror,
+        Command::CyclePivot,
+        Command::Pan,
+        Command::Console,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Create => "create",
+            Command::Extend => "extend",
+            Command::Track => "track",
+            Command::Delete => "delete",
+            Command::Unselect => "unselect",
+            Command::Click => "click",
+            Command::Cancel => "cancel",
+            Command::FrameAll => "frame-all",
+            Command::FrameSelection => "frame-selection",
+            Command::CycleUnit => "cycle-unit",
+            Command::Calibrate => "calibrate",
+            Command::IncreaseOpacity => "increase-opacity",
+            Command::DecreaseOpacity => "decrease-opacity",
+            Command::ReloadBindings => "reload-bindings",
+            Command::Save => "save",
+            Command::Open => "open",
+            Command::ToggleIssues => "toggle-issues",
+            Command::Repair => "repair",
+            Command::Undo => "undo",
+            Command::Redo => "redo",
+            Command::ToggleLog => "toggle-log",
+            Command::ScrollLogUp => "scroll-log-up",
+            Command::ScrollLogDown => "scroll-log-down",
+            Command::CycleTheme => "cycle-theme",
+            Command::ReloadTheme => "reload-theme",
+            Command::SelectTool => "select-tool",
+            Command::WallTool => "wall-tool",
+            Command::MeasureTool => "measure-tool",
+            Command::RoomTool => "room-tool",
+            Command::DoorTool => "door-tool",
+            Command::TextTool => "text-tool",
+            Command::Split => "split",
+            Command::Dissolve => "dissolve",
+            Command::Properties => "properties",
+            Command::ContextMenu => "context-menu",
+            Command::Copy => "copy",
+            Command::Cut => "cut",
+            Command::Paste => "paste",
+            Command::Duplicate => "duplicate",
+            Command::SelectAll => "select-all",
+            Command::ToggleSelection => "toggle-selection",
+            Command::Rotate => "rotate",
+            Command::Scale => "scale",
+            Command::Stretch => "stretch",
+            Command::Mirror => "mirror",
+            Command::CyclePivot => "cycle-pivot",
+            Command::Pan => "pan",
+            Command::Console => "console",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|command| command.name() == name)
+    }
+
+    // the active tool decides what plan commands do
+    pub fn actions(
+        &self,
+        tool: Tool,
+        mode: &PlanMode,
+        hover: &Hover,
+        position: Option<Vec2>,
+    ) -> Vec<Action> {
+        match self {
+            Command::Repair => vec![Action::Repair],
+            Command::Undo => vec![Action::Undo],
+            Command::Redo => vec![Action::Redo],
+            Command::SelectAll => vec![Action::SelectAll],
+            _ => tool.actions(*self, mode, hover, position),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PendingChord {
+    first: Option<Combo>,
+    started: f32,
+}
+
+impl PendingChord {
+    fn resolve(&mut self, bindings: &[&Binding], pressed: Combo, now: f32) -> Option<Command> {
+        if let Some(first) = self.first.take() {
+            if now - self.started <= CHORD_TIMEOUT {
+                let second = best_match(
+                    bindings.iter().filter(|b| b.sequence.first == first),
+                    |binding| binding.sequence.second,
+                    pressed,
+                );
+                if let Some(binding) = second {
+                    return Some(binding.command);
+                }
+            }
+        }
+        let single = best_match(
+            bindings.iter().filter(|b| !b.sequence.is_chord()),
+            |binding| Some(binding.sequence.first),
+            pressed,
+        );
+        if let Some(binding) = single {
+            return Some(binding.command);
+        }
+        let chord = best_match(
+            bindings.iter().filter(|b| b.sequence.is_chord()),
+            |binding| Some(binding.sequence.first),
+            pressed,
+        );
+        if let Some(binding) = chord {
+            self.first = Some(binding.sequence.first);
+            self.started = now;
+        }
+        None
+    }
+}
+
+// prefers the binding that requires the most modifiers
+fn best_match<'a>(
+    bindings: impl Iterator<Item = &'a &'a Binding>,
+    combo: impl Fn(&Binding) -> Option<Combo>,
+    pressed: Combo,
+) -> Option<&'a Binding> {
+    bindings
+        .filter_map(|binding| combo(binding).map(|combo| (*binding, combo)))
+        .filter(|(_, combo)| combo.matches(pressed))
+        .min_by_key(|(_, combo)| Reverse(combo.modifiers.count()))
+        .map(|(binding, _)| binding)
+}
+
+pub fn process_bindings(
+    tool: Res<Tool>,
+    plan_mode: Res<PlanMode>,
+    hover: Res<Hover>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    pan: Res<CameraPan>,
+    focus: Res<InputFocus>,
+    bindings: Res<Bindings>,
+    time: Res<Time>,
+    mouse_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    interaction_query: Query<&Interaction>,
+    mut chord: Local<PendingChord>,
+    mut action_queue: ResMut<ActionQueue>,
+    mut command_events: EventWriter<Command>,
+) {
+    if pan.is_active() || *focus != InputFocus::Plan {
+        return;
+    }
+    let active: Vec<&Binding> = bindings
+        .active(BindingContext::from_state(*tool, &plan_mode))
+        .collect();
+    let modifiers = Modifiers::pressed(&keyboard_input);
+    // mouse buttons belong to the ui while the cursor is over a widget
+    let over_ui = interaction_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    for button in Button::just_pressed(&keyboard_input, &mouse_input) {
+        if over_ui && matches!(button, Button::Mouse(_)) {
+            continue;
+        }
+        let pressed = Combo::new(modifiers, button);
+        let Some(command) = chord.resolve(&active, pressed, time.elapsed_seconds()) else {
+            continue;
+        };
+        let position = cursor.track_position(&units);
+        for action in command.actions(*tool, &plan_mode, &hover, position) {
+            action_queue.push_back(action);
+        }
+        command_events.send(command);
+        // later inputs would be resolved against a stale mode
+        break;
+    }
+}
+
+fn reload_bindings(
+    mut command_events: EventReader<Command>,
+    mut bindings: ResMut<Bindings>,
+    mut notifications: EventWriter<Notification>,
+) {
+    if command_events
+        .iter()
+        .any(|command| *command == Command::ReloadBindings)
+    {
+        let (loaded, warnings) = Bindings::load();
+        *bindings = loaded;
+        match warnings.len() {
+            0 => notifications.send(Notification::info("Reloaded key bindings")),
+            _ => notifications.send_batch(warnings.into_iter().map(Notification::warning)),
+        }
+    }
+}
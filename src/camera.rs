@@ -0,0 +1,259 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::camera::ScalingMode,
+};
+
+use crate::{
+    binding::{
+        config::{BindingContext, Bindings},
+        Command,
+    },
+    input::InputFocus,
+    plan::{analysis, document::PlanDocument, PlanMode, Selection},
+    theme::Theme,
+    tool::Tool,
+    AppSet,
+};
+
+const VIEWPORT_SIZE: f32 = 10.0;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 20.0;
+const ZOOM_STEP: f32 = 1.15;
+const PIXELS_PER_LINE: f32 = 20.0;
+const FRAME_MARGIN: f32 = 1.2;
+const FRAME_MIN_SIZE: f32 = 2.0;
+const FRAME_DURATION: f32 = 0.25;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraPan>()
+            .init_resource::<CameraFlight>()
+            .init_resource::<PixelSize>()
+            .add_startup_system(spawn_camera)
+            .add_systems(
+                (pan_camera, zoom_camera, animate_camera, update_pixel_size)
+                    .chain()
+                    .in_set(AppSet::Camera),
+            )
+            // reacts to the commands bound in the same frame
+            .add_system(frame_camera.in_set(AppSet::Action));
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct CameraPan {
+    last_position: Option<Vec2>,
+}
+
+impl CameraPan {
+    pub fn is_active(&self) -> bool {
+        self.last_position.is_some()
+    }
+}
+
+#[derive(Resource, Deref)]
+pub struct PixelSize(f32);
+
+impl Default for PixelSize {
+    fn default() -> Self {
+        Self(VIEWPORT_SIZE / 720.0)
+    }
+}
+
+#[derive(Resource, Default, Deref, DerefMut)]
+struct CameraFlight(Option<Flight>);
+
+struct Flight {
+    from: CameraView,
+    to: CameraView,
+    elapsed: f32,
+}
+
+#[derive(Clone, Copy)]
+struct CameraView {
+    position: Vec2,
+    scale: f32,
+}
+
+impl CameraView {
+    fn framing(bounds: Rect, window: &Window) -> Self {
+        let size = bounds.size().max(Vec2::splat(FRAME_MIN_SIZE)) * FRAME_MARGIN;
+        let aspect = window.width() / window.height();
+        let scale = f32::max(size.y / VIEWPORT_SIZE, size.x / (VIEWPORT_SIZE * aspect));
+        Self {
+            position: bounds.center(),
+            scale: scale.clamp(MIN_ZOOM, MAX_ZOOM),
+        }
+    }
+}
+
+pub fn pixel_size(window: &Window, projection: &OrthographicProjection) -> f32 {
+    VIEWPORT_SIZE * projection.scale / window.height()
+}
+
+// uses the local transform so it is valid in the same frame the camera moves
+pub fn viewport_to_plan(
+    window: &Window,
+    transform: &Transform,
+    projection: &OrthographicProjection,
+    screen_position: Vec2,
+) -> Vec2 {
+    let size = Vec2::new(window.width(), window.height());
+    let offset = (screen_position - size / 2.0) * pixel_size(window, projection);
+    transform.translation.truncate() + offset
+}
+
+fn spawn_camera(theme: Res<Theme>, mut commands: Commands) {
+    commands.spawn(Camera2dBundle {
+        camera_2d: Camera2d {
+            clear_color: ClearColorConfig::Custom(theme.background),
+        },
+        projection: OrthographicProjection {
+            scaling_mode: ScalingMode::FixedVertical(VIEWPORT_SIZE),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 99.9),
+        ..default()
+    });
+}
+
+fn frame_camera(
+    mut command_events: EventReader<Command>,
+    selection: Res<Selection>,
+    window_query: Query<&Window>,
+    document: Res<PlanDocument>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    mut flight: ResMut<CameraFlight>,
+) {
+    let Some(command) = command_events
+        .iter()
+        .filter(|command| matches!(command, Command::FrameAll | Command::FrameSelection))
+        .last()
+    else {
+        return;
+    };
+    let positions: Vec<Vec2> = match command {
+        Command::FrameSelection => selection
+            .points
+            .iter()
+            .filter_map(|id| document.position(*id))
+            .collect(),
+        _ => document
+            .points()
+            .map(|(_, point)| point.position())
+            .collect(),
+    };
+    let Some((min, max)) = analysis::bounds(positions) else {
+        return;
+    };
+    let window = window_query.single();
+    let (transform, projection) = camera_query.single();
+    **flight = Some(Flight {
+        from: CameraView {
+            position: transform.translation.truncate(),
+            scale: projection.scale,
+        },
+        to: CameraView::framing(Rect::from_corners(min, max), window),
+        elapsed: 0.0,
+    });
+}
+
+fn pan_camera(
+    window_query: Query<&Window>,
+    bindings: Res<Bindings>,
+    tool: Res<Tool>,
+    mode: Res<PlanMode>,
+    focus: Res<InputFocus>,
+    mouse_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+    mut pan: ResMut<CameraPan>,
+    mut flight: ResMut<CameraFlight>,
+) {
+    let window = window_query.single();
+    let context = BindingContext::from_state(*tool, &mode);
+    let dragging = *focus == InputFocus::Plan
+        && bindings.held(context, Command::Pan, &keyboard_input, &mouse_input);
+    let Some(screen_position) = window.cursor_position().filter(|_| dragging) else {
+        pan.last_position = None;
+        return;
+    };
+    **flight = None;
+    if let Some(last_position) = pan.last_position {
+        let (mut transform, projection) = camera_query.single_mut();
+        let offset = (screen_position - last_position) * pixel_size(window, projection);
+        transform.translation.x -= offset.x;
+        transform.translation.y -= offset.y;
+    }
+    pan.last_position = Some(screen_position);
+}
+
+fn zoom_camera(
+    mut wheel_events: EventReader<MouseWheel>,
+    window_query: Query<&Window>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    mut flight: ResMut<CameraFlight>,
+) {
+    let scroll: f32 = wheel_events
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if scroll == 0.0 {
+        return;
+    }
+    **flight = None;
+    let window = window_query.single();
+    let (mut transform, mut projection) = camera_query.single_mut();
+    let old_scale = projection.scale;
+    let new_scale = (old_scale * ZOOM_STEP.powf(-scroll)).clamp(MIN_ZOOM, MAX_ZOOM);
+    if let Some(screen_position) = window.cursor_position() {
+        // keep the plan position under the cursor fixed while zooming
+        let anchor = viewport_to_plan(window, &transform, &projection, screen_position);
+        let position = transform.translation.truncate();
+        let new_position = anchor + (position - anchor) * (new_scale / old_scale);
+        transform.translation.x = new_position.x;
+        transform.translation.y = new_position.y;
+    }
+    projection.scale = new_scale;
+}
+
+fn animate_camera(
+    time: Res<Time>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    mut flight: ResMut<CameraFlight>,
+) {
+    let Some(current) = &mut **flight else {
+        return;
+    };
+    current.elapsed += time.delta_seconds();
+    let t = (current.elapsed / FRAME_DURATION).min(1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    let (from, to) = (current.from, current.to);
+    let (mut transform, mut projection) = camera_query.single_mut();
+    let position = from.position.lerp(to.position, eased);
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    // interpolating the scale geometrically keeps the zoom speed perceptually constant
+    projection.scale = from.scale * (to.scale / from.scale).powf(eased);
+    if t >= 1.0 {
+        **flight = None;
+    }
+}
+
+fn update_pixel_size(
+    window_query: Query<&Window>,
+    camera_query: Query<&OrthographicProjection, With<Camera>>,
+    mut size: ResMut<PixelSize>,
+) {
+    let new_size = pixel_size(window_query.single(), camera_query.single());
+    if size.0 != new_size {
+        size.0 = new_size;
+    }
+}
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+use crate::plan::document::PlanDocument;
+
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_non_send_resource(Clipboard::new());
+    }
+}
+
+// fragments go to the system clipboard in the plan text format so they can
+// be pasted into another instance, the copy kept here covers systems
+// without a clipboard
+//
+// on X11 the copied text is served by the process that owns the clipboard
+// instance, so a single one lives as long as the app
+pub struct Clipboard {
+    system: Option<arboard::Clipboard>,
+    text: Option<String>,
+}
+
+impl Clipboard {
+    pub(crate) fn new() -> Self {
+        let system = arboard::Clipboard::new()
+            .map_err(|error| warn!("Could not open the system clipboard: {error}"))
+            .ok();
+        Self { system, text: None }
+    }
+
+    pub fn write(&mut self, text: String) {
+        if let Some(system) = &mut self.system {
+            if let Err(error) = system.set_text(text.clone()) {
+                warn!("Could not write to the system clipboard: {error}");
+            }
+        }
+        self.text = Some(text);
+    }
+
+    // unrelated text copied by another program does not hide the last fragment
+    pub fn read_plan(&mut self) -> Option<PlanDocument> {
+        let system = self
+            .system
+            .as_mut()
+            .and_then(|system| system.get_text().ok());
+        [system, self.text.clone()]
+            .into_iter()
+            .flatten()
+            .filter_map(|text| text.parse::<PlanDocument>().ok())
+            .find(|fragment| fragment.points().next().is_some())
+    }
+}
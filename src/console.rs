@@ -0,0 +1,591 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+    action::{Action, ActionQueue},
+    binding::{process_bindings, Command},
+    file::FileRequest,
+    input::{Cursor, Hover, InputFocus},
+    plan::{
+        document::{PlanDocument, PointId},
+        transform::Transformation,
+        Pivot, PlanMode, Selection, TrackMode,
+    },
+    tool::Tool,
+    units::UnitSystem,
+    AppSet,
+};
+
+const HISTORY_LENGTH: usize = 50;
+const OUTPUT_LENGTH: usize = 5;
+const KEYWORDS: [&str; 7] = ["point", "line", "extend", "move", "select", "snap", "label"];
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>().add_system(
+            update_console
+                .before(process_bindings)
+                .in_set(AppSet::Binding),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    pub input: String,
+    pub output: Vec<String>,
+    history: Vec<String>,
+    history_index: Option<usize>,
+}
+
+impl Console {
+    fn print(&mut self, message: String) {
+        self.output.push(message);
+        if self.output.len() > OUTPUT_LENGTH {
+            self.output.remove(0);
+        }
+    }
+
+    fn remember(&mut self, line: String) {
+        self.history.retain(|previous| *previous != line);
+        self.history.push(line);
+        if self.history.len() > HISTORY_LENGTH {
+            self.history.remove(0);
+        }
+        self.history_index = None;
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) => Some(index + 1).filter(|i| *i < self.history.len()),
+        };
+        self.history_index = index;
+        self.input = index.map(|i| self.history[i].clone()).unwrap_or_default();
+    }
+
+    // opens the console with the start of a command already typed
+    pub fn prompt(&mut self, input: String) {
+        self.input = input;
+        self.history_index = None;
+    }
+
+    fn complete(&mut self) {
+        if self.input.contains(char::is_whitespace) {
+            return;
+        }
+        let mut candidates: Vec<&str> = KEYWORDS
+            .into_iter()
+            .chain(Command::ALL.iter().map(|command| command.name()))
+            .filter(|name| name.starts_with(self.input.as_str()))
+            .collect();
+        // some keywords are also the names of bound commands
+        candidates.sort_unstable();
+        candidates.dedup();
+        match candidates[..] {
+            [] => (),
+            [name] => self.input = format!("{name} "),
+            _ => {
+                let prefix = candidates
+                    .iter()
+                    .skip(1)
+                    .fold(candidates[0], |prefix, name| {
+                        let length = prefix
+                            .chars()
+                            .zip(name.chars())
+                            .take_while(|(a, b)| a == b)
+                            .count();
+                        &prefix[..length]
+                    });
+                self.input = prefix.to_string();
+                self.print(candidates.join("  "));
+            }
+        }
+    }
+}
+
+struct Context<'a> {
+    tool: Tool,
+    mode: PlanMode,
+    cursor: Option<Vec2>,
+    hover: &'a Hover,
+    units: &'a UnitSystem,
+    pivot: Pivot,
+    document: &'a PlanDocument,
+    selection: &'a Selection,
+    points: Vec<(PointId, Vec2)>,
+}
+
+impl Context<'_> {
+    fn selection(&self) -> Result<(PointId, Vec2), String> {
+        let selection = self.mode.selection().ok_or("nothing is selected")?;
+        self.points
+            .iter()
+            .copied()
+            .find(|(id, _)| *id == selection)
+            .ok_or_else(|| "the selected point no longer exists".to_string())
+    }
+
+    fn base(&self) -> Vec2 {
+        self.selection()
+            .map(|(_, position)| position)
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    // x,y is absolute, @dx,dy and @length<angle are relative to the base
+    fn position(&self, text: &str, base: Vec2) -> Result<Vec2, String> {
+        let (relative, coordinates) = match text.strip_prefix('@') {
+            Some(coordinates) => (true, coordinates),
+            None => (false, text),
+        };
+        let offset = if let Some((length, angle)) = coordinates.split_once('<') {
+            let length = self.length(length)?;
+            let angle = self.angle(angle)?;
+            Vec2::new(angle.cos(), angle.sin()) * length
+        } else if let Some((x, y)) = coordinates.split_once(',') {
+            Vec2::new(self.length(x)?, self.length(y)?)
+        } else {
+            return Err(format!("invalid coordinates `{text}`"));
+        };
+        Ok(if relative { base + offset } else { offset })
+    }
+
+    fn positions(&self, texts: &[&str], mut base: Vec2) -> Result<Vec<Vec2>, String> {
+        texts
+            .iter()
+            .map(|text| {
+                base = self.position(text, base)?;
+                Ok(base)
+            })
+            .collect()
+    }
+
+    // a named pivot or coordinates, a transformation being tracked keeps its own
+    fn pivot(&self, text: Option<&str>) -> Result<Vec2, String> {
+        let (selection, _) = self.selection()?;
+        let pivot = match text {
+            None => match self.mode {
+                PlanMode::Track(_, TrackMode::Transform(_, pivot, _)) => return Ok(pivot),
+                _ => self.pivot,
+            },
+            Some("center") => Pivot::Center,
+            Some("cursor") => Pivot::Cursor,
+            Some("point") => Pivot::Point,
+            Some(text) => return self.position(text, self.base()),
+        };
+        pivot
+            .position(self.document, self.selection, selection, self.cursor)
+            .ok_or_else(|| format!("there is no {} to transform around", pivot.label()))
+    }
+
+    // degrees counterclockwise, returned in radians
+    fn angle(&self, text: &str) -> Result<f32, String> {
+        text.trim()
+            .parse::<f32>()
+            .map(f32::to_radians)
+            .map_err(|_| format!("invalid angle `{text}`"))
+    }
+
+    fn factor(&self, text: &str) -> Result<f32, String> {
+        text.parse::<f32>()
+            .map_err(|_| format!("invalid factor `{text}`"))
+    }
+
+    fn length(&self, text: &str) -> Result<f32, String> {
+        self.units
+            .parse(text)
+            .ok_or_else(|| format!("invalid length `{text}`"))
+    }
+
+    fn execute(&self, line: &str) -> Result<(Vec<Action>, Option<Command>), String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (name, args) = words.split_first().ok_or("empty command")?;
+        let actions = match (*name, args) {
+            ("point", [position]) => {
+                vec![Action::Draw(
+                    None,
+                    vec![self.position(position, self.base())?],
+                )]
+            }
+            ("line", [_, _, ..]) => vec![Action::Draw(None, self.positions(args, self.base())?)],
+            ("extend", [_, ..]) => {
+                let (selection, position) = self.selection()?;
+                vec![Action::Draw(
+                    Some(selection),
+                    self.positions(args, position)?,
+                )]
+            }
+            ("move", [position]) => {
+                let (selection, base) = self.selection()?;
+                vec![Action::Move(selection, self.position(position, base)?)]
+            }
+            ("rotate", [angle, pivot @ ..]) if pivot.len() < 2 => {
+                vec![Action::Transform(Transformation::Rotate {
+                    pivot: self.pivot(pivot.first().copied())?,
+                    angle: self.angle(angle)?,
+                })]
+            }
+            // a second number scales the y axis on its own
+            ("scale", [x, rest @ ..]) if rest.len() < 3 => {
+                let x = self.factor(x)?;
+                let (y, pivot) = match rest {
+                    [y, pivot @ ..] if y.parse::<f32>().is_ok() => (self.factor(y)?, pivot),
+                    pivot @ ([] | [_]) => (x, pivot),
+                    _ => return Err("invalid arguments for `scale`".to_string()),
+                };
+                vec![Action::Transform(Transformation::Scale {
+                    pivot: self.pivot(pivot.first().copied())?,
+                    factor: Vec2::new(x, y),
+                })]
+            }
+            ("mirror", [axis, pivot @ ..]) if pivot.len() < 2 => {
+                let direction = match *axis {
+                    "x" => Vec2::X,
+                    "y" => Vec2::Y,
+                    angle => Vec2::from_angle(self.angle(angle)?),
+                };
+                vec![Action::Transform(Transformation::Mirror {
+                    origin: self.pivot(pivot.first().copied())?,
+                    direction,
+                })]
+            }
+            ("label", [position, text @ ..]) if !text.is_empty() => {
+                vec![Action::AddLabel(
+                    self.position(position, self.base())?,
+                    text.join(" "),
+                )]
+            }
+            ("select", ["all"]) => vec![Action::SelectAll],
+            ("select", [position]) => {
+                let position = self.position(position, self.base())?;
+                let (nearest, _) = self
+                    .points
+                    .iter()
+                    .min_by(|(_, a), (_, b)| {
+                        let a = a.distance_squared(position);
+                        let b = b.distance_squared(position);
+                        a.total_cmp(&b)
+                    })
+                    .ok_or("there are no points")?;
+                vec![Action::Select(*nearest)]
+            }
+            (name, []) => {
+                let command = Command::parse(name).ok_or(format!("unknown command `{name}`"))?;
+                return Ok((
+                    command.actions(self.tool, &self.mode, self.hover, self.cursor),
+                    Some(command),
+                ));
+            }
+            (name, _) => return Err(format!("invalid arguments for `{name}`")),
+        };
+        Ok((actions, None))
+    }
+}
+
+fn update_console(
+    mut characters: EventReader<ReceivedCharacter>,
+    mut console_commands: EventReader<Command>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    tool: Res<Tool>,
+    mode: Res<PlanMode>,
+    hover: Res<Hover>,
+    cursor: Res<Cursor>,
+    mut units: ResMut<UnitSystem>,
+    pivot: Res<Pivot>,
+    document: Res<PlanDocument>,
+    selection: Res<Selection>,
+    mut focus: ResMut<InputFocus>,
+    mut console: ResMut<Console>,
+    mut action_queue: ResMut<ActionQueue>,
+    mut command_events: EventWriter<Command>,
+    mut file_requests: EventWriter<FileRequest>,
+) {
+    let typed: Vec<char> = characters.iter().map(|event| event.char).collect();
+    let open = console_commands
+        .iter()
+        .any(|command| *command == Command::Console);
+    match *focus {
+        // the characters typed while opening are left out
+        InputFocus::Plan if open => {
+            *focus = InputFocus::Console;
+            console.prompt(String::new());
+            return;
+        }
+        InputFocus::Console => (),
+        _ => return,
+    }
+    // keys that close the console are used up before the plan bindings see them
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        *focus = InputFocus::Plan;
+        return;
+    }
+    console
+        .input
+        .extend(typed.into_iter().filter(|c| !c.is_control()));
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        console.complete();
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        console.browse_history(true);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        console.browse_history(false);
+    }
+    if !keyboard_input.clear_just_pressed(KeyCode::Return) {
+        return;
+    }
+    let line = console.input.trim().to_string();
+    console.input.clear();
+    if line.is_empty() {
+        *focus = InputFocus::Plan;
+        return;
+    }
+    console.remember(line.clone());
+    // the snap steps and the plan file are set here, the rest acts on the plan
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((&line, ""));
+    let result = match (name, rest.trim()) {
+        ("snap", args) => set_snap(&mut units, &args.split_whitespace().collect::<Vec<_>>()),
+        ("save", path) if !path.is_empty() => {
+            file_requests.send(FileRequest::Save(PathBuf::from(path)));
+            Ok(())
+        }
+        ("open", path) if !path.is_empty() => {
+            file_requests.send(FileRequest::Open(PathBuf::from(path)));
+            Ok(())
+        }
+        _ => {
+            let context = Context {
+                tool: *tool,
+                mode: *mode,
+                cursor: cursor.track_position(&units),
+                hover: &hover,
+                units: &units,
+                pivot: *pivot,
+                document: &document,
+                selection: &selection,
+                points: document
+                    .points()
+                    .map(|(id, point)| (id, point.position()))
+                    .collect(),
+            };
+            context.execute(&line).map(|(actions, command)| {
+                action_queue.extend(actions);
+                if let Some(command) = command {
+                    command_events.send(command);
+                }
+            })
+        }
+    };
+    match result {
+        Ok(()) => {
+            console.print(format!("> {line}"));
+            *focus = InputFocus::Plan;
+        }
+        Err(error) => console.print(format!("{line}: {error}")),
+    }
+}
+
+fn set_snap(units: &mut UnitSystem, args: &[&str]) -> Result<(), String> {
+    match *args {
+        [step] => units.set_snap(units.parse_snap(step)?, None),
+        [step, fine_step] => {
+            let step = units.parse_snap(step)?;
+            units.set_snap(step, Some(units.parse_snap(fine_step)?))
+        }
+        _ => Err("invalid arguments for `snap`".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a and b one meter apart on the x axis, b selected
+    struct Fixture {
+        document: PlanDocument,
+        hover: Hover,
+        units: UnitSystem,
+        selection: Selection,
+        a: PointId,
+        b: PointId,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let mut document = PlanDocument::default();
+            let a = document.add_point(Vec2::ZERO);
+            let (b, _) = document.extend(a, Vec2::X).unwrap();
+            Self {
+                document,
+                hover: Hover::default(),
+                units: UnitSystem::default(),
+                selection: Selection { points: vec![b] },
+                a,
+                b,
+            }
+        }
+
+        fn context(&self, mode: PlanMode) -> Context<'_> {
+            Context {
+                tool: Tool::Select,
+                mode,
+                cursor: None,
+                hover: &self.hover,
+                units: &self.units,
+                pivot: Pivot::Point,
+                document: &self.document,
+                selection: &self.selection,
+                points: self
+                    .document
+                    .points()
+                    .map(|(id, point)| (id, point.position()))
+                    .collect(),
+            }
+        }
+
+        fn execute(&self, line: &str) -> Result<(Vec<Action>, Option<Command>), String> {
+            self.context(PlanMode::Select(self.b)).execute(line)
+        }
+    }
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn positions_are_absolute_or_relative_to_the_base() {
+        let fixture = Fixture::new();
+        let context = fixture.context(PlanMode::Default);
+        let base = Vec2::new(1.0, 1.0);
+        assert_near(context.position("2,3", base).unwrap(), Vec2::new(2.0, 3.0));
+        assert_near(context.position("@2,3", base).unwrap(), Vec2::new(3.0, 4.0));
+        assert_near(
+            context.position("@2<90", base).unwrap(),
+            Vec2::new(1.0, 3.0),
+        );
+        assert_near(
+            context.position("50cm,-1", base).unwrap(),
+            Vec2::new(0.5, -1.0),
+        );
+        assert!(context.position("2", base).is_err());
+        assert!(context.position("@x,1", base).is_err());
+        assert!(context.position("2<y", base).is_err());
+    }
+
+    #[test]
+    fn lines_chain_relative_positions() {
+        let fixture = Fixture::new();
+        let Ok((actions, None)) = fixture.execute("line 0,0 @1,0 @0,1") else {
+            panic!("`line` failed");
+        };
+        let [Action::Draw(None, positions)] = &actions[..] else {
+            panic!("`line` should draw a new line");
+        };
+        assert_eq!(positions, &[Vec2::ZERO, Vec2::X, Vec2::ONE]);
+        assert!(fixture.execute("line 0,0").is_err());
+    }
+
+    #[test]
+    fn extend_and_move_need_a_selection() {
+        let fixture = Fixture::new();
+        let Ok((actions, _)) = fixture.execute("extend @0,1") else {
+            panic!("`extend` failed");
+        };
+        assert!(
+            matches!(&actions[..], [Action::Draw(Some(id), positions)] if *id == fixture.b && positions == &[Vec2::ONE])
+        );
+        let unselected = fixture.context(PlanMode::Default);
+        assert_eq!(
+            unselected.execute("move 0,0").err().as_deref(),
+            Some("nothing is selected")
+        );
+    }
+
+    #[test]
+    fn transformations_take_an_optional_pivot() {
+        let fixture = Fixture::new();
+        let Ok((actions, _)) = fixture.execute("rotate 90") else {
+            panic!("`rotate` failed");
+        };
+        let [Action::Transform(Transformation::Rotate { pivot, angle })] = actions[..] else {
+            panic!("`rotate` should rotate");
+        };
+        assert_near(pivot, Vec2::X);
+        assert!((angle - 90_f32.to_radians()).abs() < 1e-5);
+        let Ok((actions, _)) = fixture.execute("scale 2 3 0,0") else {
+            panic!("`scale` failed");
+        };
+        assert!(matches!(
+            actions[..],
+            [Action::Transform(Transformation::Scale { pivot, factor })]
+                if pivot == Vec2::ZERO && factor == Vec2::new(2.0, 3.0)
+        ));
+        let Ok((actions, _)) = fixture.execute("mirror y center") else {
+            panic!("`mirror` failed");
+        };
+        assert!(matches!(
+            actions[..],
+            [Action::Transform(Transformation::Mirror { origin, direction })]
+                if origin == Vec2::X && direction == Vec2::Y
+        ));
+        assert!(fixture.execute("scale 2 3 0,0 1,1").is_err());
+        assert!(fixture.execute("rotate x").is_err());
+    }
+
+    #[test]
+    fn select_picks_the_nearest_point() {
+        let fixture = Fixture::new();
+        let Ok((actions, _)) = fixture.execute("select 0.2,0.3") else {
+            panic!("`select` failed");
+        };
+        assert!(matches!(actions[..], [Action::Select(id)] if id == fixture.a));
+        let Ok((actions, _)) = fixture.execute("select all") else {
+            panic!("`select all` failed");
+        };
+        assert!(matches!(actions[..], [Action::SelectAll]));
+    }
+
+    #[test]
+    fn completion_lists_each_name_once() {
+        let mut console = Console {
+            input: "ext".to_string(),
+            ..default()
+        };
+        console.complete();
+        assert_eq!(console.input, "extend ");
+        assert!(console.output.is_empty());
+        console.input = "s".to_string();
+        console.complete();
+        let listed: Vec<&str> = console.output[0].split_whitespace().collect();
+        let mut unique = listed.clone();
+        unique.dedup();
+        assert_eq!(listed, unique);
+        assert!(listed.contains(&"select") && listed.contains(&"snap"));
+    }
+
+    #[test]
+    fn bare_names_run_commands() {
+        let fixture = Fixture::new();
+        let Ok((_, command)) = fixture.execute("frame-all") else {
+            panic!("`frame-all` failed");
+        };
+        assert_eq!(command, Some(Command::FrameAll));
+        assert_eq!(
+            fixture.execute("fly").err().as_deref(),
+            Some("unknown command `fly`")
+        );
+        assert_eq!(
+            fixture.execute("point 0,0 1,1").err().as_deref(),
+            Some("invalid arguments for `point`")
+        );
+        assert!(fixture.execute("").is_err());
+    }
+}
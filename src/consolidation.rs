@@ -1,12 +1,19 @@
 use bevy::{prelude::*, sprite::Mesh2dHandle};
 
 use crate::{
-    input::{Cursor, Hover},
+    camera::PixelSize,
+    input::{Cursor, CursorMode, Hover},
     plan::{
-        line::{Line, LineShape, LINE_WIDTH},
-        point::{Point, PointAssets},
-        PlanMode,
+        document::PlanDocument,
+        door::{Door, DoorAssets, DoorBundle},
+        label::{Label, LabelAssets, LabelBundle},
+        line::{Line, LineAssets, LineBundle, LineShape},
+        point::{Point, PointAssets, PointBundle},
+        spatial::SpatialIndex,
+        HandleSizes, PlanMode, PlanView, Selection, TrackMode, TrackOrigins,
     },
+    theme::Theme,
+    units::UnitSystem,
     AppSet,
 };
 
@@ -16,39 +23,100 @@ impl Plugin for ConsolidationPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             (
-                highlight_points,
-                track_cursor_with_selection,
-                update_lines.after(track_cursor_with_selection),
+                release_missing_selection,
+                highlight_points.after(release_missing_selection),
+                highlight_lines,
+                highlight_doors_and_labels,
+                track_cursor_with_selection.after(release_missing_selection),
+                preview_transformation.after(release_missing_selection),
+                sync_view
+                    .after(track_cursor_with_selection)
+                    .after(preview_transformation),
+                scale_points.after(sync_view),
             )
                 .in_set(AppSet::Consolidation),
         );
     }
 }
 
+// the selected points can disappear through merges, repairs or undo
+fn release_missing_selection(
+    document: Res<PlanDocument>,
+    mut mode: ResMut<PlanMode>,
+    mut selection: ResMut<Selection>,
+) {
+    if let Some(id) = mode.selection() {
+        if document.point(id).is_none() {
+            *mode = PlanMode::Default;
+        }
+    }
+    selection.sync(&mode, &document);
+}
+
+// the other selected points keep their offsets to the tracked one
 fn track_cursor_with_selection(
     mode: Res<PlanMode>,
     cursor: Res<Cursor>,
-    mut query: Query<&mut Transform, With<Point>>,
+    units: Res<UnitSystem>,
+    selection: Res<Selection>,
+    mut document: ResMut<PlanDocument>,
 ) {
-    if let PlanMode::Track(entity, _) = *mode {
-        let mut transform = query.get_mut(entity).unwrap();
-        if let Some(position) = cursor.track_position() {
-            transform.translation.x = position.x;
-            transform.translation.y = position.y;
+    let PlanMode::Track(id, TrackMode::Place | TrackMode::Move(_)) = *mode else {
+        return;
+    };
+    let (Some(position), Some(old_position)) =
+        (cursor.track_position(&units), document.position(id))
+    else {
+        return;
+    };
+    if position == old_position {
+        return;
+    }
+    for other in selection.points.iter().filter(|other| **other != id) {
+        if let Some(other_position) = document.position(*other) {
+            document.move_point(*other, other_position + position - old_position);
         }
     }
+    document.move_point(id, position);
 }
 
-fn highlight_points(
+// recomputed from the origins so no error builds up while the cursor moves
+fn preview_transformation(
     mode: Res<PlanMode>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    hover: Res<Hover>,
+    origins: Res<TrackOrigins>,
+    mut document: ResMut<PlanDocument>,
+) {
+    let PlanMode::Track(_, TrackMode::Transform(kind, pivot, start)) = *mode else {
+        return;
+    };
+    let Some(position) = cursor.track_position(&units) else {
+        return;
+    };
+    let line = hover.line.and_then(|line| document.line_positions(line));
+    let snap = matches!(cursor.mode, CursorMode::Coarse);
+    let transformation = kind.transformation(pivot, start, position, line, snap);
+    for (id, origin) in origins.iter() {
+        let position =
+            transformation.map_or(*origin, |transformation| transformation.apply(*origin));
+        if document.position(*id) != Some(position) {
+            document.move_point(*id, position);
+        }
+    }
+}
+
+fn highlight_points(
+    selection: Res<Selection>,
     hover: Res<Hover>,
-    mut query: Query<(Entity, &mut Handle<ColorMaterial>), With<Point>>,
+    mut query: Query<(&Point, &mut Handle<ColorMaterial>)>,
     assets: Res<PointAssets>,
 ) {
-    for (entity, mut material) in &mut query {
-        *material = if Some(entity) == mode.selection() {
+    for (point, mut material) in &mut query {
+        *material = if selection.contains(point.id) {
             assets.selected_material.clone()
-        } else if Some(entity) == hover.point {
+        } else if Some(point.id) == hover.point {
             assets.hovered_material.clone()
         } else {
             assets.default_material.clone()
@@ -56,29 +124,199 @@ fn highlight_points(
     }
 }
 
-fn update_lines(
-    changed_point_query: Query<
-        (Entity, &Transform, &Point),
-        Or<(Changed<Transform>, Changed<Point>)>,
+fn highlight_lines(
+    hover: Res<Hover>,
+    assets: Res<LineAssets>,
+    mut query: Query<(&Line, &mut Handle<ColorMaterial>)>,
+) {
+    for (line, mut material) in &mut query {
+        let wanted = match Some(line.id) == hover.line {
+            true => &assets.hovered_material,
+            false => &assets.material,
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+}
+
+// the hovered door and label stand out, labels take the text color of the theme
+fn highlight_doors_and_labels(
+    hover: Res<Hover>,
+    theme: Res<Theme>,
+    assets: Res<DoorAssets>,
+    mut door_query: Query<(&Door, &mut Handle<ColorMaterial>)>,
+    mut label_query: Query<(&Label, &mut Text)>,
+) {
+    for (door, mut material) in &mut door_query {
+        let wanted = match Some(door.id) == hover.door {
+            true => &assets.hovered_material,
+            false => &assets.material,
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+    for (label, mut text) in &mut label_query {
+        let color = match Some(label.id) == hover.label {
+            true => theme.hover,
+            false => theme.text,
+        };
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+fn sync_view(
+    mut document: ResMut<PlanDocument>,
+    mut view: ResMut<PlanView>,
+    mut index: ResMut<SpatialIndex>,
+    sizes: Res<HandleSizes>,
+    pixel_size: Res<PixelSize>,
+    (point_assets, line_assets, door_assets, label_assets): (
+        Res<PointAssets>,
+        Res<LineAssets>,
+        Res<DoorAssets>,
+        Res<LabelAssets>,
+    ),
+    mut point_query: Query<&mut Transform, With<Point>>,
+    mut line_query: Query<(&mut Transform, &Mesh2dHandle), (With<Line>, Without<Point>)>,
+    mut door_query: Query<
+        (&mut Transform, &Mesh2dHandle),
+        (With<Door>, Without<Point>, Without<Line>),
+    >,
+    mut label_query: Query<
+        (&mut Transform, &mut Text),
+        (With<Label>, Without<Point>, Without<Line>, Without<Door>),
     >,
-    other_point_query: Query<&Transform, With<Point>>,
-    mut line_query: Query<(&mut Transform, &mut Mesh2dHandle, &Line), Without<Point>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    // draining the journal does not change the plan itself
+    let mut changes = document.bypass_change_detection().take_changes();
+    if sizes.is_changed() || pixel_size.is_changed() {
+        changes.lines.extend(document.lines().map(|(id, _)| id));
+        changes.labels.extend(document.labels().map(|(id, _)| id));
+    }
+    // doors follow the walls they are in
+    let moved_doors: Vec<_> = document
+        .doors()
+        .filter(|(_, door)| changes.lines.contains(&door.line))
+        .map(|(id, _)| id)
+        .collect();
+    changes.doors.extend(moved_doors);
+    let radius = sizes.point_radius * **pixel_size;
+    for id in changes.points {
+        let entity = view.point_entity(id);
+        index.update(id, document.position(id));
+        let Some(position) = document.position(id) else {
+            if let Some(entity) = view.remove_point(id) {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        if let Some(mut transform) = entity.and_then(|entity| point_query.get_mut(entity).ok()) {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            continue;
+        }
+        let bundle = PointBundle::new(id, &point_assets)
+            .with_position(position)
+            .with_radius(radius);
+        let entity = commands.spawn(bundle).id();
+        view.insert_point(id, entity);
+    }
+    let width = sizes.line_width * **pixel_size;
+    for id in changes.lines {
+        let entity = view.line_entity(id);
+        index.update_line(id, document.line_positions(id));
+        let Some((position_a, position_b)) = document.line_positions(id) else {
+            if let Some(entity) = view.remove_line(id) {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        let (position, local_a, local_b) = calculate_line(position_a, position_b);
+        let shape = LineShape::new(local_a, local_b, width);
+        if let Some((mut transform, line_mesh)) =
+            entity.and_then(|entity| line_query.get_mut(entity).ok())
+        {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            if let Some(mesh) = meshes.get_mut(&line_mesh.0) {
+                shape.update(mesh);
+            }
+            continue;
+        }
+        let mesh: Mesh2dHandle = meshes.add(shape.into()).into();
+        let bundle = LineBundle::new(id, &line_assets).with_shape(position, mesh);
+        let entity = commands.spawn(bundle).id();
+        view.insert_line(id, entity);
+    }
+    let door_width = sizes.door_width * **pixel_size;
+    for id in changes.doors {
+        let entity = view.door_entity(id);
+        let Some((position_a, position_b)) = document.door_positions(id) else {
+            if let Some(entity) = view.remove_door(id) {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        let (position, local_a, local_b) = calculate_line(position_a, position_b);
+        let shape = LineShape::new(local_a, local_b, door_width);
+        if let Some((mut transform, door_mesh)) =
+            entity.and_then(|entity| door_query.get_mut(entity).ok())
+        {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            if let Some(mesh) = meshes.get_mut(&door_mesh.0) {
+                shape.update(mesh);
+            }
+            continue;
+        }
+        let mesh: Mesh2dHandle = meshes.add(shape.into()).into();
+        let bundle = DoorBundle::new(id, &door_assets).with_shape(position, mesh);
+        let entity = commands.spawn(bundle).id();
+        view.insert_door(id, entity);
+    }
+    let scale = Vec3::new(**pixel_size, **pixel_size, 1.0);
+    for id in changes.labels {
+        let entity = view.label_entity(id);
+        let Some(label) = document.label(id) else {
+            if let Some(entity) = view.remove_label(id) {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        if let Some((mut transform, mut text)) =
+            entity.and_then(|entity| label_query.get_mut(entity).ok())
+        {
+            transform.translation.x = label.position.x;
+            transform.translation.y = label.position.y;
+            transform.scale = scale;
+            text.sections[0].value.clone_from(&label.text);
+            text.sections[0].style.font_size = sizes.label_size;
+            continue;
+        }
+        let bundle = LabelBundle::new(id, &label.text, sizes.label_size, &label_assets)
+            .with_position(label.position)
+            .with_scale(**pixel_size);
+        let entity = commands.spawn(bundle).id();
+        view.insert_label(id, entity);
+    }
+}
+
+fn scale_points(
+    sizes: Res<HandleSizes>,
+    pixel_size: Res<PixelSize>,
+    mut query: Query<&mut Transform, With<Point>>,
 ) {
-    for (point_a_entity, transform_a, point_a) in &changed_point_query {
-        for &line_entity in &point_a.lines {
-            let (mut line_transform, mut mesh, line) = line_query.get_mut(line_entity).unwrap();
-            let point_b_entity = line.other(point_a_entity).unwrap();
-            let transform_b = other_point_query.get(point_b_entity).unwrap();
-            let (position, local_a, local_b) = calculate_line(
-                transform_a.translation.truncate(),
-                transform_b.translation.truncate(),
-            );
-            line_transform.translation.x = position.x;
-            line_transform.translation.y = position.y;
-            *mesh = meshes
-                .add(LineShape::new(local_a, local_b, LINE_WIDTH).into())
-                .into();
+    let radius = sizes.point_radius * **pixel_size;
+    let scale = Vec3::new(radius, radius, 1.0);
+    for mut transform in &mut query {
+        if transform.scale != scale {
+            transform.scale = scale;
         }
     }
 }
@@ -0,0 +1,108 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    action::{Action, ActionQueue},
+    binding::{process_bindings, Command},
+    notification::Notification,
+    plan::document::PlanDocument,
+    AppSet,
+};
+
+const DEFAULT_PATH: &str = "plan.txt";
+
+pub struct FilePlugin;
+
+impl Plugin for FilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlanFile>()
+            .add_event::<FileRequest>()
+            .add_system(
+                handle_file_commands
+                    .after(process_bindings)
+                    .in_set(AppSet::Binding),
+            );
+    }
+}
+
+// the file the save and open commands use, it follows the last one
+// saved or opened
+#[derive(Resource)]
+pub struct PlanFile {
+    pub path: PathBuf,
+}
+
+impl Default for PlanFile {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from(DEFAULT_PATH),
+        }
+    }
+}
+
+// saving or opening a file named in the console
+#[derive(Clone, Debug)]
+pub enum FileRequest {
+    Save(PathBuf),
+    Open(PathBuf),
+}
+
+fn handle_file_commands(
+    mut command_events: EventReader<Command>,
+    mut file_requests: EventReader<FileRequest>,
+    document: Res<PlanDocument>,
+    mut plan_file: ResMut<PlanFile>,
+    mut action_queue: ResMut<ActionQueue>,
+    mut notifications: EventWriter<Notification>,
+) {
+    let commands: Vec<FileRequest> = command_events
+        .iter()
+        .filter_map(|command| match command {
+            Command::Save => Some(FileRequest::Save(plan_file.path.clone())),
+            Command::Open => Some(FileRequest::Open(plan_file.path.clone())),
+            _ => None,
+        })
+        .collect();
+    for request in commands.into_iter().chain(file_requests.iter().cloned()) {
+        match request {
+            FileRequest::Save(path) => match fs::write(&path, document.to_string()) {
+                Ok(()) => {
+                    notifications.send(Notification::info(format!("Saved {}", shown(&path))));
+                    plan_file.path = path;
+                }
+                Err(error) => notifications.send(Notification::error(format!(
+                    "Could not save {}: {error}",
+                    shown(&path)
+                ))),
+            },
+            FileRequest::Open(path) => {
+                let loaded = fs::read_to_string(&path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|source| source.parse::<PlanDocument>());
+                match loaded {
+                    Ok(loaded) => {
+                        action_queue.push_back(Action::Load(loaded));
+                        notifications.send(Notification::info(format!("Opened {}", shown(&path))));
+                        plan_file.path = path;
+                    }
+                    Err(error) => notifications.send(Notification::error(format!(
+                        "Could not open {}: {error}",
+                        shown(&path)
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+// relative paths are spelled out so it is clear which file was meant
+fn shown(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
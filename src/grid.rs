@@ -0,0 +1,105 @@
+use bevy::{prelude::*, render::render_resource::PrimitiveTopology, sprite::Mesh2dHandle};
+
+use crate::{camera::PixelSize, plan::BASE_PRIORITY, theme::Theme, AppSet};
+
+const GRID_PRIORITY: f32 = BASE_PRIORITY;
+const GRID_SPACING: f32 = 1.0;
+// lines on each side of the center until the view first changes
+const INITIAL_EXTENT: i32 = 16;
+
+pub struct GridPlugin;
+
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_grid)
+            .add_systems((follow_view, update_grid_color).in_set(AppSet::Consolidation));
+    }
+}
+
+#[derive(Component)]
+struct Grid {
+    extent: i32,
+}
+
+// a line list is drawn one pixel wide at any zoom, only the number of lines
+// depends on the view
+fn grid_mesh(extent: i32) -> Mesh {
+    let limit = extent as f32 * GRID_SPACING;
+    let mut positions = Vec::new();
+    for index in -extent..=extent {
+        let offset = index as f32 * GRID_SPACING;
+        positions.push(Vec3::new(offset, -limit, 0.0));
+        positions.push(Vec3::new(offset, limit, 0.0));
+        positions.push(Vec3::new(-limit, offset, 0.0));
+        positions.push(Vec3::new(limit, offset, 0.0));
+    }
+    let count = positions.len();
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![Vec3::Z; count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![Vec2::ZERO; count]);
+    mesh
+}
+
+fn spawn_grid(
+    theme: Res<Theme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    commands.spawn((
+        ColorMesh2dBundle {
+            mesh: meshes.add(grid_mesh(INITIAL_EXTENT)).into(),
+            material: materials.add(theme.grid.into()),
+            transform: Transform::from_translation(Vec2::ZERO.extend(GRID_PRIORITY)),
+            ..default()
+        },
+        Grid {
+            extent: INITIAL_EXTENT,
+        },
+    ));
+}
+
+// the grid is centered on the camera in whole steps so its lines stay in place,
+// and grows in powers of two to cover the visible part of the plan
+fn follow_view(
+    window_query: Query<&Window>,
+    pixel_size: Res<PixelSize>,
+    camera_query: Query<&Transform, (With<Camera>, Without<Grid>)>,
+    mut grid_query: Query<(&mut Transform, &mut Grid, &Mesh2dHandle)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let window = window_query.single();
+    let camera = camera_query.single().translation.truncate();
+    let visible = Vec2::new(window.width(), window.height()) * **pixel_size / 2.0;
+    let needed = (visible.max_element() / GRID_SPACING).ceil() as u32 + 1;
+    let extent = needed.next_power_of_two().max(INITIAL_EXTENT as u32) as i32;
+    for (mut transform, mut grid, mesh) in &mut grid_query {
+        let center = (camera / GRID_SPACING).round() * GRID_SPACING;
+        if transform.translation.truncate() != center {
+            transform.translation.x = center.x;
+            transform.translation.y = center.y;
+        }
+        if grid.extent != extent {
+            if let Some(mesh) = meshes.get_mut(&mesh.0) {
+                *mesh = grid_mesh(extent);
+            }
+            grid.extent = extent;
+        }
+    }
+}
+
+fn update_grid_color(
+    theme: Res<Theme>,
+    query: Query<&Handle<ColorMaterial>, With<Grid>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    for handle in &query {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = theme.grid;
+        }
+    }
+}
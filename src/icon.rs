@@ -0,0 +1,142 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{plan::spatial::segment_distance, tool::Tool};
+
+// icons are drawn on a grid of this many pixels, the ui scales them
+const ICON_SIZE: u32 = 32;
+const STROKE: f32 = 2.5;
+
+enum Shape {
+    // a stroke through the points
+    Path(&'static [(f32, f32)]),
+    Polygon(&'static [(f32, f32)]),
+    Dot((f32, f32), f32),
+}
+
+impl Shape {
+    // negative inside the shape
+    fn distance(&self, position: Vec2) -> f32 {
+        match self {
+            Shape::Path(points) => {
+                edges(points, false)
+                    .map(|(a, b)| segment_distance(position, a, b))
+                    .fold(f32::MAX, f32::min)
+                    - STROKE / 2.0
+            }
+            Shape::Polygon(points) => {
+                let distance = edges(points, true)
+                    .map(|(a, b)| segment_distance(position, a, b))
+                    .fold(f32::MAX, f32::min);
+                // even-odd rule, counting the edges crossed by a ray to the right
+                let crossings = edges(points, true)
+                    .filter(|(a, b)| (a.y > position.y) != (b.y > position.y))
+                    .filter(|(a, b)| {
+                        position.x < a.x + (b.x - a.x) * (position.y - a.y) / (b.y - a.y)
+                    })
+                    .count();
+                match crossings % 2 {
+                    1 => -distance,
+                    _ => distance,
+                }
+            }
+            Shape::Dot(center, radius) => position.distance(Vec2::from(*center)) - radius,
+        }
+    }
+}
+
+fn edges(points: &[(f32, f32)], closed: bool) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let count = match closed {
+        true => points.len(),
+        false => points.len() - 1,
+    };
+    (0..count).map(|index| {
+        let next = (index + 1) % points.len();
+        (Vec2::from(points[index]), Vec2::from(points[next]))
+    })
+}
+
+// in icon pixels with y pointing down
+fn shapes(tool: Tool) -> Vec<Shape> {
+    match tool {
+        Tool::Select => vec![Shape::Polygon(&[
+            (9.0, 4.0),
+            (9.0, 25.0),
+            (14.0, 20.5),
+            (18.0, 28.5),
+            (21.5, 27.0),
+            (17.5, 19.0),
+            (24.0, 19.0),
+        ])],
+        Tool::Wall => vec![
+            Shape::Path(&[(7.0, 25.0), (25.0, 7.0)]),
+            Shape::Dot((7.0, 25.0), 4.0),
+            Shape::Dot((25.0, 7.0), 4.0),
+        ],
+        Tool::Measure => vec![
+            Shape::Path(&[(5.0, 16.0), (27.0, 16.0)]),
+            Shape::Path(&[(4.0, 9.0), (4.0, 23.0)]),
+            Shape::Path(&[(28.0, 9.0), (28.0, 23.0)]),
+            Shape::Polygon(&[(5.0, 16.0), (12.0, 11.0), (12.0, 21.0)]),
+            Shape::Polygon(&[(27.0, 16.0), (20.0, 11.0), (20.0, 21.0)]),
+        ],
+        Tool::Room => vec![Shape::Path(&[
+            (6.0, 6.0),
+            (26.0, 6.0),
+            (26.0, 18.0),
+            (18.0, 18.0),
+            (18.0, 26.0),
+            (6.0, 26.0),
+            (6.0, 6.0),
+        ])],
+        // a wall with the leaf and the swing of a door hinged at its left
+        Tool::Door => vec![
+            Shape::Path(&[(3.0, 26.0), (10.0, 26.0), (10.0, 10.0)]),
+            Shape::Path(&[(26.0, 26.0), (29.0, 26.0)]),
+            Shape::Path(&[
+                (10.0, 10.0),
+                (14.1, 10.5),
+                (18.0, 12.1),
+                (21.3, 14.7),
+                (23.9, 18.0),
+                (25.5, 21.9),
+                (26.0, 26.0),
+            ]),
+        ],
+        Tool::Text => vec![
+            Shape::Path(&[(7.0, 10.0), (7.0, 6.0), (25.0, 6.0), (25.0, 10.0)]),
+            Shape::Path(&[(16.0, 6.0), (16.0, 26.0)]),
+            Shape::Path(&[(12.0, 26.0), (20.0, 26.0)]),
+        ],
+    }
+}
+
+// white on transparent so the ui can tint it with the theme colors
+pub fn tool_icon(tool: Tool) -> Image {
+    let shapes = shapes(tool);
+    let mut data = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let center = Vec2::new(x as f32, y as f32) + 0.5;
+            let distance = shapes
+                .iter()
+                .map(|shape| shape.distance(center))
+                .fold(f32::MAX, f32::min);
+            // one pixel of antialiasing along the outline
+            let coverage = (0.5 - distance).clamp(0.0, 1.0);
+            data.extend([255, 255, 255, (coverage * 255.0).round() as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: ICON_SIZE,
+            height: ICON_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
@@ -1,10 +1,13 @@
 use bevy::prelude::*;
 
 use crate::{
+    camera::{self, PixelSize},
     plan::{
-        point::{Point, POINT_RADIUS},
-        PlanMode,
+        document::{DoorId, LabelId, LineId, PlanDocument, PointId},
+        spatial::{self, SpatialIndex},
+        HandleSizes, PlanMode, Selection,
     },
+    units::UnitSystem,
     AppSet,
 };
 
@@ -14,8 +17,11 @@ impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Cursor>()
             .init_resource::<Hover>()
+            .init_resource::<InputFocus>()
             .add_systems(
-                (update_cursor_position, update_cursor_mode, update_hover).in_set(AppSet::Input),
+                (update_cursor_position, update_cursor_mode, update_hover)
+                    .chain()
+                    .in_set(AppSet::Input),
             );
     }
 }
@@ -27,85 +33,118 @@ pub struct Cursor {
 }
 
 impl Cursor {
-    pub fn track_position(&self) -> Option<Vec2> {
-        self.position.map(|position| {
-            Vec2::new(
-                Self::round(position.x, self.mode.decimals()),
-                Self::round(position.y, self.mode.decimals()),
-            )
-        })
-    }
-
-    fn round(number: f32, decimals: u32) -> f32 {
-        let offset = 10_i32.pow(decimals) as f32;
-        (number * offset).round() / offset
+    pub fn track_position(&self, units: &UnitSystem) -> Option<Vec2> {
+        let fine = matches!(self.mode, CursorMode::Fine);
+        self.position
+            .map(|position| Vec2::new(units.snap(position.x, fine), units.snap(position.y, fine)))
     }
 }
 
 #[derive(Resource, Default)]
 pub enum CursorMode {
     #[default]
-    Decimeters,
-    Centimeters,
+    Coarse,
+    Fine,
 }
 
-impl CursorMode {
-    fn decimals(&self) -> u32 {
-        match self {
-            CursorMode::Decimeters => 1,
-            CursorMode::Centimeters => 2,
-        }
-    }
+#[derive(Resource, Default, PartialEq, Eq, Debug)]
+pub enum InputFocus {
+    #[default]
+    Plan,
+    Calibration,
+    Console,
+    Inspector,
+    Menu,
 }
 
-#[derive(Resource, Default)]
+// every point in reach of the cursor, nearest first, and the nearest line
+#[derive(Resource, Default, Clone)]
 pub struct Hover {
-    pub point: Option<Entity>,
+    pub point: Option<PointId>,
+    pub candidates: Vec<PointId>,
+    pub line: Option<LineId>,
+    pub door: Option<DoorId>,
+    pub label: Option<LabelId>,
+}
+
+impl Hover {
+    // the candidate after `current`, so repeated clicks reach overlapping points
+    pub fn cycle(&self, current: PointId) -> Option<PointId> {
+        let index = self.candidates.iter().position(|id| *id == current)?;
+        let next = self.candidates[(index + 1) % self.candidates.len()];
+        (next != current).then_some(next)
+    }
 }
 
 fn update_cursor_position(
     window_query: Query<&Window>,
-    camera_query: Query<(&GlobalTransform, &Camera)>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
     mut cursor: ResMut<Cursor>,
 ) {
     let window = window_query.single();
-    let (transform, camera) = camera_query.single();
-    cursor.position = window.cursor_position().and_then(|screen_position| {
-        let size = Vec2::new(window.width(), window.height());
-        let ndc = (screen_position / size) * 2.0 - Vec2::ONE;
-        camera
-            .ndc_to_world(transform, ndc.extend(-1.0))
-            .map(|p| p.truncate())
+    let (transform, projection) = camera_query.single();
+    cursor.position = window.cursor_position().map(|screen_position| {
+        camera::viewport_to_plan(window, transform, projection, screen_position)
     });
 }
 
 fn update_cursor_mode(input: Res<Input<KeyCode>>, mut cursor: ResMut<Cursor>) {
     cursor.mode = match input.pressed(KeyCode::LAlt) || input.pressed(KeyCode::RAlt) {
-        true => CursorMode::Centimeters,
-        false => CursorMode::Decimeters,
+        true => CursorMode::Fine,
+        false => CursorMode::Coarse,
     };
 }
 
 fn update_hover(
     cursor: Res<Cursor>,
-    query: Query<(Entity, &Transform), With<Point>>,
+    index: Res<SpatialIndex>,
+    document: Res<PlanDocument>,
     mode: Res<PlanMode>,
+    selection: Res<Selection>,
+    sizes: Res<HandleSizes>,
+    pixel_size: Res<PixelSize>,
     mut hover: ResMut<Hover>,
 ) {
     let Some(cursor_position) = cursor.position else {
         return;
     };
-    let radius_squared = POINT_RADIUS * POINT_RADIUS;
-    let tracked_entity = match *mode {
-        PlanMode::Track(entity, _) => Some(entity),
-        _ => None,
+    let radius = sizes.hover_radius * **pixel_size;
+    let tracked: &[PointId] = match *mode {
+        PlanMode::Track(_, _) => &selection.points,
+        _ => &[],
     };
-    hover.point = query
-        .iter()
-        .filter(|(entity, _)| Some(*entity) != tracked_entity)
-        .find(|(_, transform)| {
-            let position = transform.translation.truncate();
-            Vec2::distance_squared(position, cursor_position) <= radius_squared
+    let mut candidates = index.within(cursor_position, radius);
+    candidates.retain(|id| !tracked.contains(id));
+    hover.point = candidates.first().copied();
+    hover.candidates = candidates;
+    // the lines of the tracked points move with the cursor and are never hovered
+    hover.line = index
+        .lines_within(cursor_position, radius)
+        .into_iter()
+        .find(|id| {
+            document
+                .line(*id)
+                .is_some_and(|line| tracked.iter().all(|id| line.other(*id).is_none()))
+        });
+    hover.door = hover.line.and_then(|line| {
+        document
+            .doors_on(line)
+            .filter_map(|id| {
+                let (a, b) = document.door_positions(id)?;
+                let distance = spatial::segment_distance(cursor_position, a, b);
+                (distance <= radius).then_some((distance, id))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, id)| id)
+    });
+    // roughly the box the text is drawn in, centered on its position
+    let size = sizes.label_size * **pixel_size;
+    hover.label = document
+        .labels()
+        .find(|(_, label)| {
+            let half = Vec2::new(label.text.chars().count() as f32 * 0.3, 0.6) * size;
+            let offset = (cursor_position - label.position).abs();
+            offset.x <= half.x && offset.y <= half.y
         })
-        .map(|(entity, _)| entity);
+        .map(|(id, _)| id);
 }
@@ -0,0 +1,281 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::{Action, ActionQueue},
+    binding::{process_bindings, Command},
+    input::InputFocus,
+    plan::{
+        document::{LineId, PlanDocument, PointId},
+        PlanMode, TrackMode,
+    },
+    units::UnitSystem,
+    AppSet,
+};
+
+const RELATIVE_PREFIX: char = '@';
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inspector>()
+            .add_system(
+                update_inspector
+                    .before(process_bindings)
+                    .in_set(AppSet::Binding),
+            )
+            // waits for the selection made by the same command
+            .add_system(open_properties.in_set(AppSet::Consolidation));
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Row {
+    Text(String),
+    Field(Field),
+}
+
+// everything the inspector shows about the selection, from top to bottom
+pub fn rows(document: &PlanDocument, mode: &PlanMode, units: &UnitSystem) -> Vec<Row> {
+    let Some((point, position)) = mode
+        .selection()
+        .and_then(|id| Some((id, document.position(id)?)))
+    else {
+        return vec![Row::Text("Nothing selected".to_string())];
+    };
+    let mut lines: Vec<(LineId, f32)> = document
+        .point(point)
+        .map_or(&[][..], |point| point.lines())
+        .iter()
+        .filter_map(|id| Some((*id, Field::Bearing(*id).value(document, point)?)))
+        .collect();
+    lines.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    let degree = match lines.len() {
+        1 => "1 line".to_string(),
+        count => format!("{count} lines"),
+    };
+    let mut rows = vec![
+        Row::Text(format!("Point {point}, {degree}")),
+        Row::Field(Field::X),
+        Row::Field(Field::Y),
+    ];
+    for (id, _) in &lines {
+        rows.push(Row::Field(Field::Length(*id)));
+        rows.push(Row::Field(Field::Bearing(*id)));
+    }
+    // with two lines only the smaller of the two angles is interesting
+    let pairs = match lines.len() {
+        0 | 1 => 0,
+        2 => 1,
+        count => count,
+    };
+    let mut angles: Vec<(LineId, LineId, f32)> = (0..pairs)
+        .map(|index| {
+            let (line_a, bearing_a) = lines[index];
+            let (line_b, bearing_b) = lines[(index + 1) % lines.len()];
+            (line_a, line_b, (bearing_b - bearing_a).rem_euclid(360.0))
+        })
+        .collect();
+    if let [(line_a, line_b, angle)] = &mut angles[..] {
+        if *angle > 180.0 {
+            (*line_a, *line_b, *angle) = (*line_b, *line_a, 360.0 - *angle);
+        }
+    }
+    for (line_a, line_b, angle) in angles {
+        rows.push(Row::Text(format!(
+            "Angle between lines {line_a} and {line_b}: {angle:.1}°"
+        )));
+    }
+    if let Some(start) = track_start(document, mode) {
+        let offset = position - start;
+        rows.push(Row::Text(format!(
+            "From start: {} at {:.1}°",
+            units.format(offset.length()),
+            bearing(offset),
+        )));
+    }
+    rows
+}
+
+// where the tracked point was picked up, or the point it was extended from
+fn track_start(document: &PlanDocument, mode: &PlanMode) -> Option<Vec2> {
+    match *mode {
+        PlanMode::Track(_, TrackMode::Move(position)) => Some(position),
+        PlanMode::Track(point, TrackMode::Place) => match document.point(point)?.lines() {
+            [line] => document.position(document.line(*line)?.other(point)?),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// degrees counterclockwise from the x axis, in [0, 360)
+fn bearing(direction: Vec2) -> f32 {
+    direction
+        .y
+        .atan2(direction.x)
+        .to_degrees()
+        .rem_euclid(360.0)
+}
+
+// line fields look from the selected point towards the other end of the line
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Field {
+    X,
+    Y,
+    Length(LineId),
+    Bearing(LineId),
+}
+
+impl Field {
+    pub fn label(&self) -> String {
+        match self {
+            Field::X => "X".to_string(),
+            Field::Y => "Y".to_string(),
+            Field::Length(id) => format!("Line {id} length"),
+            Field::Bearing(id) => format!("Line {id} bearing"),
+        }
+    }
+
+    pub fn format(&self, document: &PlanDocument, point: PointId, units: &UnitSystem) -> String {
+        match (self, self.value(document, point)) {
+            (_, None) => "-".to_string(),
+            (Field::Bearing(_), Some(value)) => format!("{value:.1}°"),
+            (_, Some(value)) => units.format(value),
+        }
+    }
+
+    fn value(&self, document: &PlanDocument, point: PointId) -> Option<f32> {
+        let position = document.position(point)?;
+        match *self {
+            Field::X => Some(position.x),
+            Field::Y => Some(position.y),
+            Field::Length(line) => Some(position.distance(self.origin(document, point, line)?)),
+            Field::Bearing(line) => Some(bearing(self.origin(document, point, line)? - position)),
+        }
+    }
+
+    fn origin(&self, document: &PlanDocument, point: PointId, line: LineId) -> Option<Vec2> {
+        document.position(document.line(line)?.other(point)?)
+    }
+
+    // `@` makes the typed value relative to the current one
+    fn apply(
+        &self,
+        text: &str,
+        document: &PlanDocument,
+        point: PointId,
+        units: &UnitSystem,
+    ) -> Result<Vec2, String> {
+        let current = self
+            .value(document, point)
+            .ok_or("the element no longer exists")?;
+        let (relative, text) = match text.trim().strip_prefix(RELATIVE_PREFIX) {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+        let typed = match self {
+            Field::Bearing(_) => text.trim().parse::<f32>().ok().filter(|a| a.is_finite()),
+            _ => units.parse(text),
+        }
+        .ok_or_else(|| format!("invalid value `{text}`"))?;
+        let value = if relative { current + typed } else { typed };
+        let position = document.position(point).unwrap_or_default();
+        Ok(match *self {
+            Field::X => Vec2::new(value, position.y),
+            Field::Y => Vec2::new(position.x, value),
+            Field::Length(line) => {
+                let origin = self.origin(document, point, line).unwrap_or_default();
+                origin + (position - origin).normalize_or_zero() * value
+            }
+            Field::Bearing(line) => {
+                let origin = self.origin(document, point, line).unwrap_or_default();
+                let angle = value.to_radians();
+                origin - Vec2::new(angle.cos(), angle.sin()) * origin.distance(position)
+            }
+        })
+    }
+}
+
+pub struct Editing {
+    pub field: Field,
+    pub text: String,
+    pub error: Option<String>,
+}
+
+#[derive(Resource, Default)]
+pub struct Inspector {
+    pub editing: Option<Editing>,
+}
+
+impl Inspector {
+    pub fn edit(&mut self, field: Field) {
+        self.editing = Some(Editing {
+            field,
+            text: String::new(),
+            error: None,
+        });
+    }
+}
+
+fn open_properties(
+    mode: Res<PlanMode>,
+    mut command_events: EventReader<Command>,
+    mut focus: ResMut<InputFocus>,
+    mut inspector: ResMut<Inspector>,
+) {
+    let open = command_events
+        .iter()
+        .any(|command| *command == Command::Properties);
+    if open && mode.selection().is_some() && *focus == InputFocus::Plan {
+        inspector.edit(Field::X);
+        *focus = InputFocus::Inspector;
+    }
+}
+
+fn update_inspector(
+    mut characters: EventReader<ReceivedCharacter>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mode: Res<PlanMode>,
+    document: Res<PlanDocument>,
+    units: Res<UnitSystem>,
+    mut focus: ResMut<InputFocus>,
+    mut inspector: ResMut<Inspector>,
+    mut action_queue: ResMut<ActionQueue>,
+) {
+    let typed: Vec<char> = characters.iter().map(|event| event.char).collect();
+    if *focus != InputFocus::Inspector {
+        return;
+    }
+    let (Some(selection), Some(editing)) = (mode.selection(), inspector.editing.as_mut()) else {
+        inspector.editing = None;
+        *focus = InputFocus::Plan;
+        return;
+    };
+    // the key that ends editing is not passed on to the plan bindings
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        inspector.editing = None;
+        *focus = InputFocus::Plan;
+        return;
+    }
+    editing
+        .text
+        .extend(typed.into_iter().filter(|c| !c.is_control()));
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        editing.text.pop();
+    }
+    if !keyboard_input.clear_just_pressed(KeyCode::Return) {
+        return;
+    }
+    match editing
+        .field
+        .apply(&editing.text, &document, selection, &units)
+    {
+        Ok(position) => {
+            action_queue.push_back(Action::Move(selection, position));
+            inspector.editing = None;
+            *focus = InputFocus::Plan;
+        }
+        Err(error) => editing.error = Some(error),
+    }
+}
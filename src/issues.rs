@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+
+use crate::{
+    binding::Command,
+    notification::Notification,
+    plan::{
+        document::PlanDocument,
+        integrity::{self, Issue},
+        PlanMode,
+    },
+    AppSet,
+};
+
+pub struct IssuesPlugin;
+
+impl Plugin for IssuesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IssueList>().add_systems(
+            (toggle_issue_list, update_issue_list)
+                .chain()
+                .in_set(AppSet::Consolidation),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct IssueList {
+    pub open: bool,
+    pub issues: Vec<Issue>,
+}
+
+fn toggle_issue_list(
+    mut command_events: EventReader<Command>,
+    document: Res<PlanDocument>,
+    mut list: ResMut<IssueList>,
+    mut notifications: EventWriter<Notification>,
+) {
+    for command in command_events.iter() {
+        if *command == Command::ToggleIssues {
+            list.open = !list.open;
+            list.issues = match list.open {
+                true => integrity::check(&document),
+                false => Vec::new(),
+            };
+            if list.open {
+                notifications.send(match list.issues.len() {
+                    0 => Notification::info("The plan has no issues"),
+                    count => Notification::warning(format!("The plan has {count} issues")),
+                });
+            }
+        }
+    }
+}
+
+// tracking changes the plan every frame, it is checked again once the tracking ends
+fn update_issue_list(
+    document: Res<PlanDocument>,
+    mode: Res<PlanMode>,
+    mut outdated: Local<bool>,
+    mut list: ResMut<IssueList>,
+) {
+    *outdated = list.open && (*outdated || document.is_changed());
+    if !*outdated || matches!(*mode, PlanMode::Track(_, _)) {
+        return;
+    }
+    *outdated = false;
+    list.issues = integrity::check(&document);
+}
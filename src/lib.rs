@@ -2,23 +2,44 @@
 
 mod action;
 mod binding;
+mod camera;
+mod clipboard;
+mod console;
 mod consolidation;
+mod file;
+mod grid;
+mod icon;
 mod input;
+mod inspector;
+mod issues;
+mod menu;
+mod notification;
 mod palette;
 mod plan;
+mod theme;
+mod tool;
 mod ui;
+mod underlay;
+mod units;
 
-use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*, render::camera::ScalingMode};
+use bevy::prelude::*;
 
 use self::{
-    action::ActionPlugin, binding::BindingPlugin, consolidation::ConsolidationPlugin,
-    input::InputPlugin, plan::PlanPlugin, ui::UiPlugin,
+    action::ActionPlugin, binding::BindingPlugin, camera::CameraPlugin, clipboard::ClipboardPlugin,
+    console::ConsolePlugin, consolidation::ConsolidationPlugin, file::FilePlugin, grid::GridPlugin,
+    input::InputPlugin, inspector::InspectorPlugin, issues::IssuesPlugin, menu::MenuPlugin,
+    notification::NotificationPlugin, plan::PlanPlugin, theme::ThemePlugin, tool::ToolPlugin,
+    ui::UiPlugin, underlay::UnderlayPlugin, units::UnitsPlugin,
 };
 
-const VIEWPORT_SIZE: f32 = 10.0;
+pub use self::{
+    plan::{analysis, document, export, integrity, line::LineShape, repair, spatial},
+    units::{Unit, UnitSystem},
+};
 
 #[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum AppSet {
+    Camera,
     Input,
     Binding,
     Action,
@@ -32,6 +53,7 @@ impl Plugin for AppPlugin {
     fn build(&self, app: &mut App) {
         app.configure_sets(
             (
+                AppSet::Camera,
                 AppSet::Input,
                 AppSet::Binding,
                 AppSet::Action,
@@ -40,26 +62,24 @@ impl Plugin for AppPlugin {
             )
                 .chain(),
         )
+        .add_plugin(ThemePlugin)
         .add_plugin(PlanPlugin)
+        .add_plugin(CameraPlugin)
         .add_plugin(InputPlugin)
         .add_plugin(BindingPlugin)
+        .add_plugin(ToolPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(ConsolePlugin)
+        .add_plugin(InspectorPlugin)
+        .add_plugin(FilePlugin)
+        .add_plugin(ClipboardPlugin)
         .add_plugin(ActionPlugin)
         .add_plugin(ConsolidationPlugin)
-        .add_plugin(UiPlugin)
-        .add_startup_system(setup);
+        .add_plugin(IssuesPlugin)
+        .add_plugin(NotificationPlugin)
+        .add_plugin(UnitsPlugin)
+        .add_plugin(UnderlayPlugin)
+        .add_plugin(GridPlugin)
+        .add_plugin(UiPlugin);
     }
 }
-
-fn setup(mut commands: Commands) {
-    commands.spawn(Camera2dBundle {
-        camera_2d: Camera2d {
-            clear_color: ClearColorConfig::Custom(palette::DARK_BLACK),
-        },
-        projection: OrthographicProjection {
-            scaling_mode: ScalingMode::FixedVertical(VIEWPORT_SIZE),
-            ..default()
-        },
-        transform: Transform::from_xyz(0.0, 0.0, 99.9),
-        ..default()
-    });
-}
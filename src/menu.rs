@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::Action,
+    binding::{
+        config::{BindingContext, Bindings},
+        process_bindings, Command,
+    },
+    input::{Cursor, Hover, InputFocus},
+    plan::PlanMode,
+    tool::Tool,
+    units::UnitSystem,
+    AppSet,
+};
+
+// offered when the active tool turns them into actions for the target
+const ENTRIES: [(Command, &str); 15] = [
+    (Command::Track, "Move"),
+    (Command::Extend, "Extend"),
+    (Command::Properties, "Properties"),
+    (Command::Dissolve, "Dissolve"),
+    (Command::Split, "Split"),
+    (Command::Copy, "Copy"),
+    (Command::Cut, "Cut"),
+    (Command::Duplicate, "Duplicate"),
+    (Command::Rotate, "Rotate"),
+    (Command::Scale, "Scale"),
+    (Command::Stretch, "Stretch"),
+    (Command::Mirror, "Mirror"),
+    (Command::Delete, "Delete"),
+    (Command::Create, "New point"),
+    (Command::Paste, "Paste here"),
+];
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContextMenu>().add_systems(
+            (
+                close_context_menu.before(process_bindings),
+                open_context_menu.after(process_bindings),
+            )
+                .in_set(AppSet::Binding),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ContextMenu {
+    pub open: Option<Menu>,
+}
+
+// the target is kept as the mode and hover it would have when picked directly
+pub struct Menu {
+    pub screen_position: Vec2,
+    pub title: String,
+    pub entries: Vec<MenuEntry>,
+    tool: Tool,
+    mode: PlanMode,
+    hover: Hover,
+    position: Option<Vec2>,
+}
+
+pub struct MenuEntry {
+    pub label: &'static str,
+    pub shortcut: Option<String>,
+    command: Command,
+}
+
+impl Menu {
+    // a point target is selected first, which keeps the selection around it
+    pub fn run(&self, index: usize) -> Option<(Vec<Action>, Command)> {
+        let command = self.entries.get(index)?.command;
+        let mut actions: Vec<Action> = self
+            .mode
+            .selection()
+            .map(Action::Select)
+            .into_iter()
+            .collect();
+        actions.extend(command.actions(self.tool, &self.mode, &self.hover, self.position));
+        Some((actions, command))
+    }
+}
+
+fn open_context_menu(
+    tool: Res<Tool>,
+    mode: Res<PlanMode>,
+    hover: Res<Hover>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    bindings: Res<Bindings>,
+    window_query: Query<&Window>,
+    mut command_events: EventReader<Command>,
+    mut menu: ResMut<ContextMenu>,
+    mut focus: ResMut<InputFocus>,
+) {
+    if !command_events
+        .iter()
+        .any(|command| *command == Command::ContextMenu)
+    {
+        return;
+    }
+    // a point being placed or moved has to be finished first
+    let (PlanMode::Default | PlanMode::Select(_)) = *mode else {
+        return;
+    };
+    let Some(screen_position) = window_query.single().cursor_position() else {
+        return;
+    };
+    let (title, target_mode, target_hover) = match (hover.point, hover.line) {
+        (Some(point), _) => (
+            format!("Point {point}"),
+            PlanMode::Select(point),
+            Hover {
+                point: Some(point),
+                candidates: vec![point],
+                ..default()
+            },
+        ),
+        (None, Some(line)) => (
+            format!("Line {line}"),
+            PlanMode::Default,
+            Hover {
+                line: Some(line),
+                door: hover.door,
+                ..default()
+            },
+        ),
+        (None, None) => match hover.label {
+            Some(label) => (
+                format!("Label {label}"),
+                PlanMode::Default,
+                Hover {
+                    label: Some(label),
+                    ..default()
+                },
+            ),
+            None => ("Plan".to_string(), PlanMode::Default, Hover::default()),
+        },
+    };
+    let position = cursor.track_position(&units);
+    let context = BindingContext::from_state(*tool, &target_mode);
+    let entries: Vec<MenuEntry> = ENTRIES
+        .into_iter()
+        .filter(|(command, _)| {
+            !command
+                .actions(*tool, &target_mode, &target_hover, position)
+                .is_empty()
+        })
+        .map(|(command, label)| MenuEntry {
+            label,
+            shortcut: bindings
+                .active(context)
+                .find(|binding| binding.command == command)
+                .map(|binding| binding.sequence.to_string()),
+            command,
+        })
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+    menu.open = Some(Menu {
+        screen_position,
+        title,
+        entries,
+        tool: *tool,
+        mode: target_mode,
+        hover: target_hover,
+        position,
+    });
+    *focus = InputFocus::Menu;
+}
+
+// picking an entry is handled by the ui, any other click dismisses the menu
+// and is used up so it does not act on the plan as well
+fn close_context_menu(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    interaction_query: Query<&Interaction>,
+    mut menu: ResMut<ContextMenu>,
+    mut focus: ResMut<InputFocus>,
+) {
+    if menu.open.is_none() {
+        return;
+    }
+    let over_ui = interaction_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    let buttons = [MouseButton::Left, MouseButton::Right];
+    let clicked_outside = !over_ui && mouse_input.any_just_pressed(buttons);
+    if clicked_outside {
+        for button in buttons {
+            mouse_input.clear_just_pressed(button);
+        }
+    }
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) || clicked_outside {
+        menu.open = None;
+        *focus = InputFocus::Plan;
+    }
+}
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use chrono::{DateTime, Local};
+
+use crate::{action::ActionError, binding::Command, AppSet};
+
+const LOG_LENGTH: usize = 500;
+pub const LOG_LINES: usize = 12;
+pub const MAX_TOASTS: usize = 5;
+pub const TOAST_DURATION: f32 = 4.0;
+
+pub struct NotificationPlugin;
+
+impl Plugin for NotificationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Messages>()
+            .add_event::<Notification>()
+            .add_systems(
+                (report_action_errors, collect_notifications, update_log)
+                    .chain()
+                    .in_set(AppSet::Consolidation),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+// any plugin can send these to inform the user
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+}
+
+impl Notification {
+    pub fn info(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Info,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warning,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Error,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub level: Level,
+    pub text: String,
+    // seconds since startup, for timing out toasts
+    pub time: f32,
+    // local time for the log
+    pub received: DateTime<Local>,
+}
+
+impl Message {
+    pub fn timestamp(&self) -> String {
+        self.received.format("%H:%M:%S").to_string()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Messages {
+    pub log: Vec<Message>,
+    pub log_open: bool,
+    // lines scrolled up from the newest message
+    pub scroll: usize,
+}
+
+impl Messages {
+    pub fn toasts(&self, now: f32) -> impl Iterator<Item = &Message> {
+        let recent = self
+            .log
+            .iter()
+            .rev()
+            .take_while(move |message| now - message.time < TOAST_DURATION)
+            .count()
+            .min(MAX_TOASTS);
+        self.log[self.log.len() - recent..].iter()
+    }
+
+    pub fn visible_log(&self) -> &[Message] {
+        let end = self.log.len() - self.scroll.min(self.log.len());
+        &self.log[end.saturating_sub(LOG_LINES)..end]
+    }
+
+    fn scroll(&mut self, lines: isize) {
+        let max = self.log.len().saturating_sub(LOG_LINES);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+}
+
+fn report_action_errors(
+    mut error_events: EventReader<ActionError>,
+    mut notifications: EventWriter<Notification>,
+) {
+    for error in error_events.iter() {
+        notifications.send(Notification::warning(format!("Skipped: {error}")));
+    }
+}
+
+fn collect_notifications(
+    time: Res<Time>,
+    mut notifications: EventReader<Notification>,
+    mut messages: ResMut<Messages>,
+) {
+    for notification in notifications.iter() {
+        match notification.level {
+            Level::Info => info!("{}", notification.message),
+            Level::Warning => warn!("{}", notification.message),
+            Level::Error => error!("{}", notification.message),
+        }
+        messages.log.push(Message {
+            level: notification.level,
+            text: notification.message.clone(),
+            time: time.elapsed_seconds(),
+            received: Local::now(),
+        });
+        if messages.log.len() > LOG_LENGTH {
+            messages.log.remove(0);
+        }
+    }
+}
+
+fn update_log(mut command_events: EventReader<Command>, mut messages: ResMut<Messages>) {
+    for command in command_events.iter() {
+        match command {
+            Command::ToggleLog => {
+                messages.log_open = !messages.log_open;
+                messages.scroll = 0;
+            }
+            Command::ScrollLogUp if messages.log_open => messages.scroll(LOG_LINES as isize / 2),
+            Command::ScrollLogDown if messages.log_open => {
+                messages.scroll(-(LOG_LINES as isize) / 2)
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::Vec2;
+
+use super::document::{LineId, PlanDocument, PointId};
+
+const MIN_ROOM_AREA: f32 = 1e-6;
+
+#[derive(Clone, Debug)]
+pub struct Room {
+    pub points: Vec<PointId>,
+    pub area: f32,
+}
+
+impl Room {
+    // even-odd test against the outline
+    pub fn contains(&self, document: &PlanDocument, position: Vec2) -> bool {
+        let positions: Vec<Vec2> = self
+            .points
+            .iter()
+            .filter_map(|id| document.position(*id))
+            .collect();
+        let count = positions.len();
+        (0..count)
+            .filter(|&i| {
+                let (a, b) = (positions[i], positions[(i + 1) % count]);
+                (a.y > position.y) != (b.y > position.y)
+                    && position.x < a.x + (position.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+pub fn line_length(document: &PlanDocument, id: LineId) -> Option<f32> {
+    let (position_a, position_b) = document.line_positions(id)?;
+    Some(position_a.distance(position_b))
+}
+
+pub fn wall_length(document: &PlanDocument) -> f32 {
+    document
+        .lines()
+        .filter_map(|(id, _)| line_length(document, id))
+        .sum()
+}
+
+// rooms are the bounded faces of the wall graph, found by always taking the
+// sharpest clockwise turn, which walks every bounded face counterclockwise
+pub fn rooms(document: &PlanDocument) -> Vec<Room> {
+    let mut neighbors: HashMap<PointId, Vec<(f32, PointId)>> = HashMap::new();
+    for (_, line) in document.lines() {
+        let (Some(a), Some(b)) = (
+            document.position(line.point_a),
+            document.position(line.point_b),
+        ) else {
+            continue;
+        };
+        if line.point_a == line.point_b || a == b {
+            continue;
+        }
+        neighbors
+            .entry(line.point_a)
+            .or_default()
+            .push((angle(b - a), line.point_b));
+        neighbors
+            .entry(line.point_b)
+            .or_default()
+            .push((angle(a - b), line.point_a));
+    }
+    for edges in neighbors.values_mut() {
+        edges.sort_by(|a, b| a.0.total_cmp(&b.0));
+        edges.dedup_by_key(|edge| edge.1);
+    }
+    let half_edges: usize = neighbors.values().map(Vec::len).sum();
+    let mut visited = HashSet::new();
+    let mut rooms = Vec::new();
+    let mut starts: Vec<(PointId, PointId)> = neighbors
+        .iter()
+        .flat_map(|(from, edges)| edges.iter().map(|(_, to)| (*from, *to)))
+        .collect();
+    starts.sort();
+    for start in starts {
+        if visited.contains(&start) {
+            continue;
+        }
+        let mut points = Vec::new();
+        let (mut from, mut to) = start;
+        while visited.insert((from, to)) && points.len() <= half_edges {
+            points.push(from);
+            let edges = &neighbors[&to];
+            let back = edges.iter().position(|(_, id)| *id == from).unwrap();
+            let next = edges[(back + edges.len() - 1) % edges.len()].1;
+            (from, to) = (to, next);
+        }
+        let positions: Vec<Vec2> = points
+            .iter()
+            .filter_map(|id| document.position(*id))
+            .collect();
+        let area = signed_area(&positions);
+        if (from, to) == start && area > MIN_ROOM_AREA {
+            rooms.push(Room { points, area });
+        }
+    }
+    rooms
+}
+
+// the innermost room around the position
+pub fn room_at(document: &PlanDocument, position: Vec2) -> Option<Room> {
+    innermost(&rooms(document), document, position).cloned()
+}
+
+// the smallest of the rooms around the position
+pub fn innermost<'a>(
+    rooms: &'a [Room],
+    document: &PlanDocument,
+    position: Vec2,
+) -> Option<&'a Room> {
+    rooms
+        .iter()
+        .filter(|room| room.contains(document, position))
+        .min_by(|a, b| a.area.total_cmp(&b.area))
+}
+
+// the corners of the smallest box around the positions
+pub fn bounds(positions: impl IntoIterator<Item = Vec2>) -> Option<(Vec2, Vec2)> {
+    positions.into_iter().fold(None, |bounds, position| {
+        Some(match bounds {
+            Some((min, max)) => (min.min(position), max.max(position)),
+            None => (position, position),
+        })
+    })
+}
+
+fn angle(direction: Vec2) -> f32 {
+    direction.y.atan2(direction.x)
+}
+
+fn signed_area(positions: &[Vec2]) -> f32 {
+    let count = positions.len();
+    (0..count)
+        .map(|i| positions[i].perp_dot(positions[(i + 1) % count]))
+        .sum::<f32>()
+        / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(document: &mut PlanDocument, min: Vec2, size: f32) -> Vec<PointId> {
+        let corners = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y];
+        let points: Vec<PointId> = corners
+            .iter()
+            .map(|corner| document.add_point(min + *corner * size))
+            .collect();
+        for (index, point) in points.iter().enumerate() {
+            document.add_line(*point, points[(index + 1) % points.len()]);
+        }
+        points
+    }
+
+    #[test]
+    fn dangling_lines_and_self_loops_are_not_rooms() {
+        let mut document = PlanDocument::default();
+        let points = square(&mut document, Vec2::ZERO, 2.0);
+        document.extend(points[2], Vec2::new(3.0, 3.0));
+        let (inside, _) = document.extend(points[0], Vec2::new(1.0, 1.0)).unwrap();
+        document.add_line(points[1], points[1]);
+        let rooms = rooms(&document);
+        assert_eq!(rooms.len(), 1);
+        assert!((rooms[0].area - 4.0).abs() < 1e-5);
+        // the outline runs out and back along a wall sticking into the room
+        let mut corners = rooms[0].points.clone();
+        corners.sort();
+        corners.dedup();
+        assert_eq!(corners, [&points[..], &[inside]].concat());
+        assert!((wall_length(&document) - 8.0 - 2.0_f32.sqrt() * 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn a_shared_wall_splits_two_rooms() {
+        let mut document = PlanDocument::default();
+        let points = square(&mut document, Vec2::ZERO, 2.0);
+        let b = points[1];
+        let bottom = document.line_between(points[0], b).unwrap();
+        let a = document.split_line(bottom, Vec2::new(1.0, 0.0)).unwrap();
+        let top = document.line_between(points[2], points[3]).unwrap();
+        let c = document.split_line(top, Vec2::new(1.0, 2.0)).unwrap();
+        document.add_line(a, c);
+        let rooms = rooms(&document);
+        assert_eq!(rooms.len(), 2);
+        assert!(rooms.iter().all(|room| (room.area - 2.0).abs() < 1e-5));
+        let left = room_at(&document, Vec2::new(0.5, 1.0)).unwrap();
+        assert!(left.points.contains(&points[0]) && !left.points.contains(&b));
+        let right = room_at(&document, Vec2::new(1.5, 1.0)).unwrap();
+        assert!(right.points.contains(&b) && !right.points.contains(&points[0]));
+        assert!(room_at(&document, Vec2::new(3.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn the_innermost_room_wins() {
+        let mut document = PlanDocument::default();
+        square(&mut document, Vec2::ZERO, 10.0);
+        let inner = square(&mut document, Vec2::new(4.0, 4.0), 2.0);
+        assert_eq!(rooms(&document).len(), 2);
+        let room = room_at(&document, Vec2::new(5.0, 5.0)).unwrap();
+        assert!((room.area - 4.0).abs() < 1e-5);
+        assert!(room.points.iter().all(|id| inner.contains(id)));
+        let room = room_at(&document, Vec2::new(1.0, 1.0)).unwrap();
+        assert!((room.area - 100.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn bounds_cover_every_position() {
+        assert_eq!(bounds([]), None);
+        let positions = [Vec2::new(1.0, -2.0), Vec2::new(-3.0, 4.0), Vec2::ZERO];
+        assert_eq!(
+            bounds(positions),
+            Some((Vec2::new(-3.0, -2.0), Vec2::new(1.0, 4.0)))
+        );
+    }
+}
@@ -0,0 +1,792 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
+
+use glam::Vec2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct PointId(u64);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct LineId(u64);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct DoorId(u64);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct LabelId(u64);
+
+impl fmt::Display for PointId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for LineId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for DoorId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for LabelId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlanPoint {
+    position: Vec2,
+    lines: Vec<LineId>,
+}
+
+impl PlanPoint {
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn lines(&self) -> &[LineId] {
+        &self.lines
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PlanLine {
+    pub point_a: PointId,
+    pub point_b: PointId,
+}
+
+impl PlanLine {
+    pub fn other(&self, point: PointId) -> Option<PointId> {
+        if point == self.point_a {
+            Some(self.point_b)
+        } else if point == self.point_b {
+            Some(self.point_a)
+        } else {
+            None
+        }
+    }
+
+    fn replace(&mut self, old: PointId, new: PointId) {
+        if old == self.point_a {
+            self.point_a = new;
+        }
+        if old == self.point_b {
+            self.point_b = new;
+        }
+    }
+}
+
+// an opening in a wall, centered at `offset` along the line from `point_a`
+// to `point_b` as a fraction of its length so it follows the wall around
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PlanDoor {
+    pub line: LineId,
+    pub offset: f32,
+    pub width: f32,
+}
+
+// a text centered on its position, whitespace is collapsed to single spaces
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlanLabel {
+    pub position: Vec2,
+    pub text: String,
+}
+
+// elements touched since the last call to `PlanDocument::take_changes`
+#[derive(Clone, Default, Debug)]
+pub struct Changes {
+    pub points: BTreeSet<PointId>,
+    pub lines: BTreeSet<LineId>,
+    pub doors: BTreeSet<DoorId>,
+    pub labels: BTreeSet<LabelId>,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct PlanDocument {
+    points: BTreeMap<PointId, PlanPoint>,
+    lines: BTreeMap<LineId, PlanLine>,
+    doors: BTreeMap<DoorId, PlanDoor>,
+    labels: BTreeMap<LabelId, PlanLabel>,
+    next_id: u64,
+    changes: Changes,
+}
+
+impl PlanDocument {
+    pub fn point(&self, id: PointId) -> Option<&PlanPoint> {
+        self.points.get(&id)
+    }
+
+    pub fn line(&self, id: LineId) -> Option<&PlanLine> {
+        self.lines.get(&id)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = (PointId, &PlanPoint)> {
+        self.points.iter().map(|(id, point)| (*id, point))
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = (LineId, &PlanLine)> {
+        self.lines.iter().map(|(id, line)| (*id, line))
+    }
+
+    pub fn door(&self, id: DoorId) -> Option<&PlanDoor> {
+        self.doors.get(&id)
+    }
+
+    pub fn doors(&self) -> impl Iterator<Item = (DoorId, &PlanDoor)> {
+        self.doors.iter().map(|(id, door)| (*id, door))
+    }
+
+    pub fn doors_on(&self, line: LineId) -> impl Iterator<Item = DoorId> + '_ {
+        self.doors()
+            .filter(move |(_, door)| door.line == line)
+            .map(|(id, _)| id)
+    }
+
+    pub fn label(&self, id: LabelId) -> Option<&PlanLabel> {
+        self.labels.get(&id)
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = (LabelId, &PlanLabel)> {
+        self.labels.iter().map(|(id, label)| (*id, label))
+    }
+
+    pub fn line_between(&self, point_a: PointId, point_b: PointId) -> Option<LineId> {
+        self.point(point_a)?.lines.iter().copied().find(|id| {
+            self.line(*id)
+                .is_some_and(|line| line.other(point_a) == Some(point_b))
+        })
+    }
+
+    pub fn position(&self, id: PointId) -> Option<Vec2> {
+        self.point(id).map(PlanPoint::position)
+    }
+
+    pub fn line_positions(&self, id: LineId) -> Option<(Vec2, Vec2)> {
+        let line = self.line(id)?;
+        Some((self.position(line.point_a)?, self.position(line.point_b)?))
+    }
+
+    // the ends of the opening, a door wider than its wall fills the wall
+    pub fn door_positions(&self, id: DoorId) -> Option<(Vec2, Vec2)> {
+        let door = self.door(id)?;
+        let (a, b) = self.line_positions(door.line)?;
+        let center = a.lerp(b, door.offset);
+        let half = (b - a).normalize_or_zero() * door.width.min(a.distance(b)) / 2.0;
+        Some((center - half, center + half))
+    }
+
+    pub fn add_point(&mut self, position: Vec2) -> PointId {
+        let id = PointId(self.allocate());
+        let point = PlanPoint {
+            position,
+            lines: Vec::new(),
+        };
+        self.points.insert(id, point);
+        self.changes.points.insert(id);
+        id
+    }
+
+    pub fn add_line(&mut self, point_a: PointId, point_b: PointId) -> Option<LineId> {
+        if !self.points.contains_key(&point_a) || !self.points.contains_key(&point_b) {
+            return None;
+        }
+        let id = LineId(self.allocate());
+        self.lines.insert(id, PlanLine { point_a, point_b });
+        for point_id in [point_a, point_b] {
+            self.points.get_mut(&point_id)?.lines.push(id);
+        }
+        self.changes.lines.insert(id);
+        Some(id)
+    }
+
+    pub fn extend(&mut self, from: PointId, position: Vec2) -> Option<(PointId, LineId)> {
+        if !self.points.contains_key(&from) {
+            return None;
+        }
+        let point = self.add_point(position);
+        let line = self.add_line(from, point)?;
+        Some((point, line))
+    }
+
+    pub fn move_point(&mut self, id: PointId, position: Vec2) -> bool {
+        let Some(point) = self.points.get_mut(&id) else {
+            return false;
+        };
+        point.position = position;
+        self.changes.points.insert(id);
+        self.changes.lines.extend(point.lines.iter().copied());
+        true
+    }
+
+    pub fn remove_line(&mut self, id: LineId) -> bool {
+        let Some(line) = self.lines.remove(&id) else {
+            return false;
+        };
+        self.remove_doors_on(id);
+        for point_id in [line.point_a, line.point_b] {
+            if let Some(point) = self.points.get_mut(&point_id) {
+                point.lines.retain(|line_id| *line_id != id);
+            }
+        }
+        self.changes.lines.insert(id);
+        true
+    }
+
+    pub fn remove_point(&mut self, id: PointId) -> bool {
+        let Some(point) = self.points.get(&id) else {
+            return false;
+        };
+        for line_id in point.lines.clone() {
+            self.remove_line(line_id);
+        }
+        self.points.remove(&id);
+        self.changes.points.insert(id);
+        true
+    }
+
+    // moves every line of `from` over to `into` and removes `from`, lines that
+    // would collapse into a self-loop or duplicate an existing line are dropped
+    pub fn merge_points(&mut self, from: PointId, into: PointId) -> bool {
+        if from == into || !self.points.contains_key(&into) {
+            return false;
+        }
+        let Some(old_point) = self.points.remove(&from) else {
+            return false;
+        };
+        for line_id in old_point.lines {
+            let Some(line) = self.lines.get_mut(&line_id) else {
+                continue;
+            };
+            line.replace(from, into);
+            let line = *line;
+            let other = line.other(into).unwrap();
+            if other == into || self.line_between(into, other).is_some() {
+                self.lines.remove(&line_id);
+                self.remove_doors_on(line_id);
+                if let Some(other_point) = self.points.get_mut(&other) {
+                    other_point.lines.retain(|id| *id != line_id);
+                }
+            } else if let Some(new_point) = self.points.get_mut(&into) {
+                new_point.lines.push(line_id);
+            }
+            self.changes.lines.insert(line_id);
+        }
+        self.changes.points.insert(from);
+        self.changes.points.insert(into);
+        true
+    }
+
+    // replaces a line by two lines meeting at a new point, doors move to the
+    // part their center is on
+    pub fn split_line(&mut self, id: LineId, position: Vec2) -> Option<PointId> {
+        let line = *self.lines.get(&id)?;
+        let (a, b) = self.line_positions(id)?;
+        let split = match (b - a).length_squared() {
+            length if length > 0.0 => ((position - a).dot(b - a) / length).clamp(0.0, 1.0),
+            _ => 0.0,
+        };
+        let doors: Vec<(DoorId, PlanDoor)> = self
+            .doors_on(id)
+            .map(|door_id| (door_id, self.doors[&door_id]))
+            .collect();
+        self.remove_line(id);
+        let point = self.add_point(position);
+        let line_a = self.add_line(line.point_a, point)?;
+        let line_b = self.add_line(point, line.point_b)?;
+        for (door_id, mut door) in doors {
+            (door.line, door.offset) = match door.offset < split || split == 1.0 {
+                true => (line_a, door.offset / split.max(f32::EPSILON)),
+                false => (line_b, (door.offset - split) / (1.0 - split)),
+            };
+            self.doors.insert(door_id, door);
+            self.changes.doors.insert(door_id);
+        }
+        Some(point)
+    }
+
+    // `None` when the line does not exist or the door has no size
+    pub fn add_door(&mut self, line: LineId, offset: f32, width: f32) -> Option<DoorId> {
+        let valid = width > 0.0 && width.is_finite() && offset.is_finite();
+        if !valid || !self.lines.contains_key(&line) {
+            return None;
+        }
+        let id = DoorId(self.allocate());
+        let offset = offset.clamp(0.0, 1.0);
+        self.doors.insert(
+            id,
+            PlanDoor {
+                line,
+                offset,
+                width,
+            },
+        );
+        self.changes.doors.insert(id);
+        Some(id)
+    }
+
+    pub fn remove_door(&mut self, id: DoorId) -> bool {
+        if self.doors.remove(&id).is_none() {
+            return false;
+        }
+        self.changes.doors.insert(id);
+        true
+    }
+
+    fn remove_doors_on(&mut self, line: LineId) {
+        let ids: Vec<DoorId> = self.doors_on(line).collect();
+        for id in ids {
+            self.remove_door(id);
+        }
+    }
+
+    pub fn add_label(&mut self, position: Vec2, text: &str) -> LabelId {
+        let id = LabelId(self.allocate());
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.labels.insert(id, PlanLabel { position, text });
+        self.changes.labels.insert(id);
+        id
+    }
+
+    pub fn remove_label(&mut self, id: LabelId) -> bool {
+        if self.labels.remove(&id).is_none() {
+            return false;
+        }
+        self.changes.labels.insert(id);
+        true
+    }
+
+    // the neighbors of a point between exactly two lines, which can be joined
+    // directly when the point is dissolved
+    pub fn dissolvable(&self, id: PointId) -> Option<(PointId, PointId)> {
+        let [line_a, line_b] = self.point(id)?.lines[..] else {
+            return None;
+        };
+        let a = self.line(line_a)?.other(id)?;
+        let b = self.line(line_b)?.other(id)?;
+        (a != b && a != id && b != id).then_some((a, b))
+    }
+
+    pub fn dissolve_point(&mut self, id: PointId) -> bool {
+        let Some((a, b)) = self.dissolvable(id) else {
+            return false;
+        };
+        self.remove_point(id);
+        if self.line_between(a, b).is_none() {
+            self.add_line(a, b);
+        }
+        true
+    }
+
+    // a copy of the points, of the lines between them and of the doors in
+    // those lines, with new ids
+    pub fn extract(&self, points: &[PointId]) -> PlanDocument {
+        let mut fragment = PlanDocument::default();
+        let mut ids = BTreeMap::new();
+        for &id in points {
+            if let (Some(position), false) = (self.position(id), ids.contains_key(&id)) {
+                ids.insert(id, fragment.add_point(position));
+            }
+        }
+        for (id, line) in self.lines() {
+            let (Some(a), Some(b)) = (ids.get(&line.point_a), ids.get(&line.point_b)) else {
+                continue;
+            };
+            let Some(new_id) = fragment.add_line(*a, *b) else {
+                continue;
+            };
+            for door in self.doors_on(id).map(|door_id| self.doors[&door_id]) {
+                fragment.add_door(new_id, door.offset, door.width);
+            }
+        }
+        fragment.take_changes();
+        fragment
+    }
+
+    // adds every element of the fragment moved by the offset and returns the
+    // new ids of its points in the order of the old ones
+    pub fn insert(&mut self, fragment: &PlanDocument, offset: Vec2) -> Vec<PointId> {
+        let ids: BTreeMap<PointId, PointId> = fragment
+            .points()
+            .map(|(id, point)| (id, self.add_point(point.position() + offset)))
+            .collect();
+        let mut lines = BTreeMap::new();
+        for (id, line) in fragment.lines() {
+            if let (Some(a), Some(b)) = (ids.get(&line.point_a), ids.get(&line.point_b)) {
+                if let Some(new_id) = self.add_line(*a, *b) {
+                    lines.insert(id, new_id);
+                }
+            }
+        }
+        for (_, door) in fragment.doors() {
+            if let Some(line) = lines.get(&door.line) {
+                self.add_door(*line, door.offset, door.width);
+            }
+        }
+        for (_, label) in fragment.labels() {
+            self.add_label(label.position + offset, &label.text);
+        }
+        ids.into_values().collect()
+    }
+
+    // swaps in another document, every element of both is reported as changed
+    // and ids are never handed out twice
+    pub fn replace(&mut self, other: PlanDocument) {
+        let old = std::mem::replace(self, other);
+        self.next_id = self.next_id.max(old.next_id);
+        let points = old.points.keys().chain(self.points.keys()).copied();
+        let lines = old.lines.keys().chain(self.lines.keys()).copied();
+        let doors = old.doors.keys().chain(self.doors.keys()).copied();
+        let labels = old.labels.keys().chain(self.labels.keys()).copied();
+        self.changes.points.extend(points.collect::<Vec<_>>());
+        self.changes.lines.extend(lines.collect::<Vec<_>>());
+        self.changes.doors.extend(doors.collect::<Vec<_>>());
+        self.changes.labels.extend(labels.collect::<Vec<_>>());
+    }
+
+    // compares the elements only, ids still to be allocated and the change
+    // journal do not matter
+    pub fn same_elements(&self, other: &PlanDocument) -> bool {
+        self.points == other.points
+            && self.lines == other.lines
+            && self.doors == other.doors
+            && self.labels == other.labels
+    }
+
+    pub fn take_changes(&mut self) -> Changes {
+        std::mem::take(&mut self.changes)
+    }
+
+    fn allocate(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+// one element per line, ids are kept so that references survive a save and load
+impl fmt::Display for PlanDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (id, point) in &self.points {
+            let position = point.position;
+            writeln!(f, "point {id} {} {}", position.x, position.y)?;
+        }
+        for (id, line) in &self.lines {
+            writeln!(f, "line {id} {} {}", line.point_a, line.point_b)?;
+        }
+        for (id, door) in &self.doors {
+            writeln!(f, "door {id} {} {} {}", door.line, door.offset, door.width)?;
+        }
+        for (id, label) in &self.labels {
+            let position = label.position;
+            writeln!(f, "label {id} {} {} {}", position.x, position.y, label.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PlanDocument {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut document = Self::default();
+        for (index, line) in source.lines().enumerate() {
+            // the text of a label runs to the end of its line
+            let line = match line.trim_start().starts_with("label ") {
+                true => line.trim(),
+                false => line.split('#').next().unwrap_or_default().trim(),
+            };
+            if line.is_empty() {
+                continue;
+            }
+            document
+                .parse_element(line)
+                .map_err(|error| format!("line {}: {error}", index + 1))?;
+        }
+        Ok(document)
+    }
+}
+
+impl PlanDocument {
+    fn parse_element(&mut self, line: &str) -> Result<(), String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let id = match words[..] {
+            ["point", id, x, y] => {
+                let id = self.parse_new_id(id)?;
+                let position = Vec2::new(parse_number(x)?, parse_number(y)?);
+                let point = PlanPoint {
+                    position,
+                    lines: Vec::new(),
+                };
+                self.points.insert(PointId(id), point);
+                id
+            }
+            ["line", id, point_a, point_b] => {
+                let id = self.parse_new_id(id)?;
+                let point_a = self.parse_point_id(point_a)?;
+                let point_b = self.parse_point_id(point_b)?;
+                self.lines.insert(LineId(id), PlanLine { point_a, point_b });
+                for point_id in [point_a, point_b] {
+                    if let Some(point) = self.points.get_mut(&point_id) {
+                        point.lines.push(LineId(id));
+                    }
+                }
+                id
+            }
+            ["door", id, line, offset, width] => {
+                let id = self.parse_new_id(id)?;
+                let line = self.parse_line_id(line)?;
+                let offset = parse_number(offset)?;
+                let width = parse_number(width)?;
+                if !(0.0..=1.0).contains(&offset) || width <= 0.0 {
+                    return Err("doors need an offset from 0 to 1 and a width".into());
+                }
+                self.doors.insert(
+                    DoorId(id),
+                    PlanDoor {
+                        line,
+                        offset,
+                        width,
+                    },
+                );
+                id
+            }
+            ["label", id, x, y, ref text @ ..] if !text.is_empty() => {
+                let id = self.parse_new_id(id)?;
+                let position = Vec2::new(parse_number(x)?, parse_number(y)?);
+                let text = text.join(" ");
+                self.labels
+                    .insert(LabelId(id), PlanLabel { position, text });
+                id
+            }
+            _ => {
+                return Err(
+                    "expected `point <id> <x> <y>`, `line <id> <point> <point>`, \
+                     `door <id> <line> <offset> <width>` or `label <id> <x> <y> <text>`"
+                        .into(),
+                )
+            }
+        };
+        self.next_id = self.next_id.max(id);
+        Ok(())
+    }
+
+    fn parse_new_id(&self, text: &str) -> Result<u64, String> {
+        let id = text
+            .parse::<u64>()
+            .map_err(|_| format!("invalid id `{text}`"))?;
+        let used = self.points.contains_key(&PointId(id))
+            || self.lines.contains_key(&LineId(id))
+            || self.doors.contains_key(&DoorId(id))
+            || self.labels.contains_key(&LabelId(id));
+        if used {
+            return Err(format!("duplicated id `{id}`"));
+        }
+        Ok(id)
+    }
+
+    fn parse_point_id(&self, text: &str) -> Result<PointId, String> {
+        let id = text
+            .parse::<u64>()
+            .map(PointId)
+            .map_err(|_| format!("invalid id `{text}`"))?;
+        match self.points.contains_key(&id) {
+            true => Ok(id),
+            false => Err(format!("unknown point `{text}`")),
+        }
+    }
+
+    fn parse_line_id(&self, text: &str) -> Result<LineId, String> {
+        let id = text
+            .parse::<u64>()
+            .map(LineId)
+            .map_err(|_| format!("invalid id `{text}`"))?;
+        match self.lines.contains_key(&id) {
+            true => Ok(id),
+            false => Err(format!("unknown line `{text}`")),
+        }
+    }
+}
+
+fn parse_number(text: &str) -> Result<f32, String> {
+    text.parse::<f32>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("invalid number `{text}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a - b - c in a row along the x axis
+    fn path() -> (PlanDocument, [PointId; 3], [LineId; 2]) {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(0.0, 0.0));
+        let (b, ab) = document.extend(a, Vec2::new(1.0, 0.0)).unwrap();
+        let (c, bc) = document.extend(b, Vec2::new(2.0, 0.0)).unwrap();
+        document.take_changes();
+        (document, [a, b, c], [ab, bc])
+    }
+
+    #[test]
+    fn add_and_extend() {
+        let (document, [a, b, c], [ab, bc]) = path();
+        assert_eq!(document.points().count(), 3);
+        assert_eq!(document.position(c), Some(Vec2::new(2.0, 0.0)));
+        assert_eq!(document.point(b).unwrap().lines(), &[ab, bc]);
+        assert_eq!(document.line_between(a, b), Some(ab));
+        assert_eq!(document.line_between(a, c), None);
+    }
+
+    #[test]
+    fn add_line_needs_both_points() {
+        let (mut document, [a, _, c], _) = path();
+        document.remove_point(c);
+        assert_eq!(document.add_line(a, c), None);
+        assert_eq!(document.extend(c, Vec2::ZERO), None);
+    }
+
+    #[test]
+    fn remove_point_removes_its_lines() {
+        let (mut document, [a, b, c], [ab, bc]) = path();
+        assert!(document.remove_point(b));
+        assert!(!document.remove_point(b));
+        assert!(document.line(ab).is_none() && document.line(bc).is_none());
+        assert!(document.point(a).unwrap().lines().is_empty());
+        assert!(document.point(c).unwrap().lines().is_empty());
+    }
+
+    #[test]
+    fn merge_moves_lines_and_drops_collapsed_ones() {
+        let (mut document, [a, b, c], [ab, bc]) = path();
+        document.add_line(a, c);
+        assert!(document.merge_points(b, c));
+        assert!(document.point(b).is_none());
+        // a - b became a - c, which already existed, and b - c collapsed
+        assert!(document.line(ab).is_none() && document.line(bc).is_none());
+        assert_eq!(document.lines().count(), 1);
+        assert!(!document.merge_points(a, a));
+    }
+
+    #[test]
+    fn split_and_dissolve() {
+        let (mut document, [a, b, _], [ab, _]) = path();
+        let middle = document.split_line(ab, Vec2::new(0.5, 0.0)).unwrap();
+        assert!(document.line(ab).is_none());
+        assert!(document.line_between(a, middle).is_some());
+        assert!(document.line_between(middle, b).is_some());
+        assert_eq!(document.dissolvable(middle), Some((a, b)));
+        assert!(document.dissolve_point(middle));
+        assert!(document.line_between(a, b).is_some());
+        assert_eq!(document.dissolvable(a), None);
+        assert!(!document.dissolve_point(a));
+    }
+
+    #[test]
+    fn extract_and_insert() {
+        let (mut document, [a, b, _], _) = path();
+        let fragment = document.extract(&[a, b]);
+        assert_eq!(fragment.points().count(), 2);
+        assert_eq!(fragment.lines().count(), 1);
+        let ids = document.insert(&fragment, Vec2::new(0.0, 5.0));
+        assert_eq!(ids.len(), 2);
+        assert_eq!(document.position(ids[0]), Some(Vec2::new(0.0, 5.0)));
+        assert_eq!(document.position(ids[1]), Some(Vec2::new(1.0, 5.0)));
+        assert!(document.line_between(ids[0], ids[1]).is_some());
+        assert_eq!(document.points().count(), 5);
+    }
+
+    #[test]
+    fn changes_are_journaled() {
+        let (mut document, [_, b, _], [ab, bc]) = path();
+        assert!(document.take_changes().points.is_empty());
+        document.move_point(b, Vec2::new(1.0, 1.0));
+        let changes = document.take_changes();
+        assert_eq!(changes.points.into_iter().collect::<Vec<_>>(), vec![b]);
+        assert_eq!(changes.lines.into_iter().collect::<Vec<_>>(), vec![ab, bc]);
+        assert!(document.take_changes().lines.is_empty());
+    }
+
+    #[test]
+    fn replace_reports_both_documents_and_keeps_ids_unique() {
+        let (mut document, [a, _, _], _) = path();
+        document.replace(PlanDocument::default());
+        assert_eq!(document.take_changes().points.len(), 3);
+        let new = document.add_point(Vec2::ZERO);
+        assert!(new > a);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let (document, [_, _, c], _) = path();
+        let text = document.to_string();
+        let mut parsed: PlanDocument = text.parse().unwrap();
+        assert!(parsed.same_elements(&document));
+        assert_eq!(parsed.to_string(), text);
+        // loaded ids are not handed out again
+        assert!(parsed.add_point(Vec2::ZERO) > c);
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let error = "point 1 0 0\n# comment\npoint 2 x 0"
+            .parse::<PlanDocument>()
+            .unwrap_err();
+        assert!(error.starts_with("line 3:"), "{error}");
+        assert!("line 3 1 2".parse::<PlanDocument>().is_err());
+    }
+
+    #[test]
+    fn doors_follow_their_lines() {
+        let (mut document, [a, b, _], [ab, bc]) = path();
+        let door = document.add_door(ab, 0.25, 0.2).unwrap();
+        assert_eq!(
+            document.door_positions(door),
+            Some((Vec2::new(0.15, 0.0), Vec2::new(0.35, 0.0)))
+        );
+        assert!(document.add_door(ab, 0.5, 0.0).is_none());
+        // the door moves to the part its center is on
+        let middle = document.split_line(ab, Vec2::new(0.5, 0.0)).unwrap();
+        let first = document.line_between(a, middle).unwrap();
+        assert_eq!(document.door(door).map(|door| door.line), Some(first));
+        let (start, end) = document.door_positions(door).unwrap();
+        assert!(start.distance(Vec2::new(0.15, 0.0)) < 1e-6);
+        assert!(end.distance(Vec2::new(0.35, 0.0)) < 1e-6);
+        // doors go with their walls
+        let other = document.add_door(bc, 0.5, 2.0).unwrap();
+        assert_eq!(
+            document.door_positions(other),
+            Some((Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0)))
+        );
+        document.take_changes();
+        document.remove_point(b);
+        assert!(document.door(other).is_none());
+        assert!(document.take_changes().doors.contains(&other));
+        assert!(document.door(door).is_some());
+    }
+
+    #[test]
+    fn doors_and_labels_round_trip() {
+        let (mut document, _, [ab, _]) = path();
+        document.add_door(ab, 0.5, 0.9);
+        let label = document.add_label(Vec2::new(1.0, 2.0), "  Room #1\tkitchen ");
+        assert_eq!(document.label(label).unwrap().text, "Room #1 kitchen");
+        let text = document.to_string();
+        let parsed: PlanDocument = text.parse().unwrap();
+        assert!(parsed.same_elements(&document));
+        assert_eq!(parsed.to_string(), text);
+        let fragment = document.extract(&document.points().map(|(id, _)| id).collect::<Vec<_>>());
+        assert_eq!(fragment.doors().count(), 1);
+        assert!("point 1 0 0\ndoor 2 1 0.5 1"
+            .parse::<PlanDocument>()
+            .is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use bevy::{prelude::*, sprite::Mesh2dHandle};
+
+use crate::plan::{document::DoorId, line::LINE_PRIORITY};
+
+// drawn over the wall it is in
+const DOOR_PRIORITY: f32 = LINE_PRIORITY + 0.5;
+
+pub struct DoorPlugin;
+
+impl Plugin for DoorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DoorAssets>();
+    }
+}
+
+#[derive(Resource)]
+pub struct DoorAssets {
+    pub material: Handle<ColorMaterial>,
+    pub hovered_material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for DoorAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        // colored by the theme
+        Self {
+            material: materials.add(ColorMaterial::default()),
+            hovered_material: materials.add(ColorMaterial::default()),
+        }
+    }
+}
+
+// the mesh is a `LineShape` across the opening
+#[derive(Bundle)]
+pub struct DoorBundle {
+    material_mesh: ColorMesh2dBundle,
+    door: Door,
+}
+
+impl DoorBundle {
+    pub fn new(id: DoorId, assets: &DoorAssets) -> Self {
+        Self {
+            material_mesh: ColorMesh2dBundle {
+                material: assets.material.clone(),
+                transform: Transform::from_translation(Vec2::ZERO.extend(DOOR_PRIORITY)),
+                ..default()
+            },
+            door: Door { id },
+        }
+    }
+
+    pub fn with_shape(mut self, position: Vec2, mesh: Mesh2dHandle) -> Self {
+        let translation = &mut self.material_mesh.transform.translation;
+        translation.x = position.x;
+        translation.y = position.y;
+        self.material_mesh.mesh = mesh;
+        self
+    }
+}
+
+#[derive(Component)]
+pub struct Door {
+    pub id: DoorId,
+}
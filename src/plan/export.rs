@@ -0,0 +1,192 @@
+use std::fmt::Write;
+
+use super::{analysis, document::PlanDocument, unit::Unit};
+
+const SVG_MARGIN: f32 = 0.5;
+const SVG_LINE_WIDTH: f32 = 0.02;
+const SVG_POINT_RADIUS: f32 = 0.04;
+// doors are drawn as gaps in the walls
+const SVG_DOOR_WIDTH: f32 = 0.06;
+const SVG_LABEL_SIZE: f32 = 0.25;
+// printed at 1:100, or a quarter inch to the foot for imperial units
+const SVG_CENTIMETERS_PER_METER: f32 = 1.0;
+const SVG_INCHES_PER_METER: f32 = 0.25 / 0.3048;
+
+// plan coordinates are in meters with y pointing up, svg has y pointing down
+pub fn to_svg(document: &PlanDocument, unit: Unit) -> String {
+    let positions = document.points().map(|(_, point)| point.position());
+    let labels = document.labels().map(|(_, label)| label.position);
+    let (min, max) = analysis::bounds(positions.chain(labels)).unwrap_or_default();
+    let size = max - min + 2.0 * SVG_MARGIN;
+    let min = min - SVG_MARGIN;
+    let (paper, symbol) = match unit.is_imperial() {
+        true => (size * SVG_INCHES_PER_METER, "in"),
+        false => (size * SVG_CENTIMETERS_PER_METER, "cm"),
+    };
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}{symbol}\" height=\"{}{symbol}\">",
+        min.x,
+        flip(min.y + size.y),
+        size.x,
+        size.y,
+        paper.x,
+        paper.y,
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        "  <g stroke=\"black\" stroke-width=\"{SVG_LINE_WIDTH}\">"
+    )
+    .unwrap();
+    for (id, _) in document.lines() {
+        if let Some((a, b)) = document.line_positions(id) {
+            writeln!(
+                svg,
+                "    <line id=\"line-{id}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                a.x,
+                flip(a.y),
+                b.x,
+                flip(b.y),
+            )
+            .unwrap();
+        }
+    }
+    writeln!(svg, "  </g>").unwrap();
+    writeln!(
+        svg,
+        "  <g stroke=\"white\" stroke-width=\"{SVG_DOOR_WIDTH}\">"
+    )
+    .unwrap();
+    for (id, _) in document.doors() {
+        if let Some((a, b)) = document.door_positions(id) {
+            writeln!(
+                svg,
+                "    <line id=\"door-{id}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                a.x,
+                flip(a.y),
+                b.x,
+                flip(b.y),
+            )
+            .unwrap();
+        }
+    }
+    writeln!(svg, "  </g>").unwrap();
+    for (id, point) in document.points() {
+        let position = point.position();
+        writeln!(
+            svg,
+            "  <circle id=\"point-{id}\" cx=\"{}\" cy=\"{}\" r=\"{SVG_POINT_RADIUS}\"/>",
+            position.x,
+            flip(position.y),
+        )
+        .unwrap();
+    }
+    for (id, label) in document.labels() {
+        writeln!(
+            svg,
+            "  <text id=\"label-{id}\" x=\"{}\" y=\"{}\" font-size=\"{SVG_LABEL_SIZE}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",
+            label.position.x,
+            flip(label.position.y),
+            escape(&label.text),
+        )
+        .unwrap();
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn flip(y: f32) -> f32 {
+    // avoids printing "-0"
+    0.0 - y
+}
+
+// one row per wall, coordinates and lengths in the unit named in the header
+pub fn to_csv(document: &PlanDocument, unit: Unit) -> String {
+    let symbol = unit.number_symbol();
+    let mut csv = format!(
+        "line,point_a,point_b,x_a_{symbol},y_a_{symbol},x_b_{symbol},y_b_{symbol},length_{symbol}\n"
+    );
+    for (id, line) in document.lines() {
+        let (Some((a, b)), Some(length)) = (
+            document.line_positions(id),
+            analysis::line_length(document, id),
+        ) else {
+            continue;
+        };
+        let [x_a, y_a, x_b, y_b, length] =
+            [a.x, a.y, b.x, b.y, length].map(|meters| unit.format_number(meters));
+        writeln!(
+            csv,
+            "{id},{},{},{x_a},{y_a},{x_b},{y_b},{length}",
+            line.point_a, line.point_b,
+        )
+        .unwrap();
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+
+    #[test]
+    fn svg_draws_walls_doors_and_labels() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::ZERO);
+        let (_, line) = document.extend(a, Vec2::new(4.0, 0.0)).unwrap();
+        let door = document.add_door(line, 0.5, 1.0).unwrap();
+        let label = document.add_label(Vec2::new(2.0, 1.0), "A & <B>");
+        let svg = to_svg(&document, Unit::Meters);
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-0.5 -1.5 5 2\" width=\"5cm\" height=\"2cm\">"
+        ));
+        assert!(svg.contains(&format!(
+            "<line id=\"line-{line}\" x1=\"0\" y1=\"0\" x2=\"4\" y2=\"0\"/>"
+        )));
+        assert!(svg.contains(&format!(
+            "<line id=\"door-{door}\" x1=\"1.5\" y1=\"0\" x2=\"2.5\" y2=\"0\"/>"
+        )));
+        assert!(svg.contains(&format!("<circle id=\"point-{a}\" cx=\"0\" cy=\"0\"")));
+        assert!(svg.contains(&format!("<text id=\"label-{label}\" x=\"2\" y=\"-1\"")));
+        assert!(svg.contains(">A &amp; &lt;B&gt;</text>"));
+        assert!(svg.ends_with("</svg>\n"));
+        // printed at a quarter inch to the foot
+        let svg = to_svg(&document, Unit::FeetInches);
+        let width = 5.0 * SVG_INCHES_PER_METER;
+        assert!(svg.contains(&format!("width=\"{width}in\"")));
+    }
+
+    #[test]
+    fn an_empty_svg_has_only_the_margin() {
+        let svg = to_svg(&PlanDocument::default(), Unit::Meters);
+        assert_eq!(
+            svg.lines().next(),
+            Some("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-0.5 -0.5 1 1\" width=\"1cm\" height=\"1cm\">")
+        );
+    }
+
+    #[test]
+    fn csv_has_a_row_per_wall_in_the_unit() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::ZERO);
+        let (b, line) = document.extend(a, Vec2::new(3.0, 4.0)).unwrap();
+        assert_eq!(
+            to_csv(&document, Unit::Centimeters),
+            format!(
+                "line,point_a,point_b,x_a_cm,y_a_cm,x_b_cm,y_b_cm,length_cm\n{line},{a},{b},0,0,300,400,500\n"
+            )
+        );
+        let header = to_csv(&document, Unit::FeetInches);
+        assert!(header.starts_with("line,point_a,point_b,x_a_in,y_a_in,"));
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use glam::Vec2;
+
+use super::{
+    document::{LineId, PlanDocument, PointId},
+    spatial::SpatialIndex,
+};
+
+// distances in meters below which elements are considered to coincide
+pub const TOLERANCE: f32 = 0.005;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IssueKind {
+    ZeroLengthLine,
+    SelfLoop,
+    DuplicateLines,
+    IsolatedPoint,
+    OverlappingLines,
+    CoincidentPoints,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Element {
+    Point(PointId),
+    Line(LineId),
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Element::Point(id) => write!(f, "point {id}"),
+            Element::Line(id) => write!(f, "line {id}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub elements: Vec<Element>,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self.kind {
+            IssueKind::ZeroLengthLine => "zero-length line",
+            IssueKind::SelfLoop => "line connects a point to itself",
+            IssueKind::DuplicateLines => "duplicated lines",
+            IssueKind::IsolatedPoint => "point without lines",
+            IssueKind::OverlappingLines => "overlapping lines",
+            IssueKind::CoincidentPoints => "coincident points",
+        };
+        let elements: Vec<String> = self.elements.iter().map(ToString::to_string).collect();
+        write!(f, "{description}: {}", elements.join(", "))
+    }
+}
+
+pub fn check(document: &PlanDocument) -> Vec<Issue> {
+    let index = SpatialIndex::from_document(document);
+    let mut issues = Vec::new();
+    check_lines(document, &index, &mut issues);
+    check_points(document, &index, &mut issues);
+    issues
+}
+
+fn check_lines(document: &PlanDocument, index: &SpatialIndex, issues: &mut Vec<Issue>) {
+    let mut pairs: HashMap<(PointId, PointId), LineId> = HashMap::new();
+    let mut segments = BTreeMap::new();
+    for (id, line) in document.lines() {
+        let issue = |kind| Issue {
+            kind,
+            elements: vec![Element::Line(id)],
+        };
+        if line.point_a == line.point_b {
+            issues.push(issue(IssueKind::SelfLoop));
+            continue;
+        }
+        let Some((a, b)) = document.line_positions(id) else {
+            continue;
+        };
+        if a.distance(b) < TOLERANCE {
+            issues.push(issue(IssueKind::ZeroLengthLine));
+            continue;
+        }
+        let pair = (
+            line.point_a.min(line.point_b),
+            line.point_a.max(line.point_b),
+        );
+        if let Some(original) = pairs.insert(pair, id) {
+            issues.push(Issue {
+                kind: IssueKind::DuplicateLines,
+                elements: vec![Element::Line(original), Element::Line(id)],
+            });
+            continue;
+        }
+        segments.insert(id, (a, b));
+    }
+    // only lines passing the same cells can overlap
+    for (id, (a, b)) in &segments {
+        for other_id in index.lines_near(*a, *b, TOLERANCE) {
+            let Some((c, d)) = segments.get(&other_id).filter(|_| other_id > *id) else {
+                continue;
+            };
+            if overlap(*a, *b, *c, *d) {
+                issues.push(Issue {
+                    kind: IssueKind::OverlappingLines,
+                    elements: vec![Element::Line(*id), Element::Line(other_id)],
+                });
+            }
+        }
+    }
+}
+
+fn check_points(document: &PlanDocument, index: &SpatialIndex, issues: &mut Vec<Issue>) {
+    for (id, point) in document.points() {
+        if point.lines().is_empty() {
+            issues.push(Issue {
+                kind: IssueKind::IsolatedPoint,
+                elements: vec![Element::Point(id)],
+            });
+        }
+        let position = point.position();
+        let mut coincident: Vec<PointId> = index
+            .within(position, TOLERANCE)
+            .into_iter()
+            .filter(|other_id| *other_id > id)
+            .filter(|other_id| {
+                document
+                    .position(*other_id)
+                    .is_some_and(|other| position.distance(other) < TOLERANCE)
+            })
+            .collect();
+        coincident.sort_unstable();
+        for other_id in coincident {
+            issues.push(Issue {
+                kind: IssueKind::CoincidentPoints,
+                elements: vec![Element::Point(id), Element::Point(other_id)],
+            });
+        }
+    }
+}
+
+// collinear segments sharing more than a single end point
+fn overlap(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let direction = (b - a).normalize();
+    let distance = |p: Vec2| direction.perp_dot(p - a).abs();
+    if distance(c) > TOLERANCE || distance(d) > TOLERANCE {
+        return false;
+    }
+    let project = |p: Vec2| direction.dot(p - a);
+    let (start, end) = (project(c).min(project(d)), project(c).max(project(d)));
+    end.min(a.distance(b)) - start.max(0.0) > TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(issues: &[Issue]) -> Vec<(IssueKind, Vec<Element>)> {
+        issues
+            .iter()
+            .map(|issue| (issue.kind, issue.elements.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn coincident_points_across_cells() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(0.999, 2.0));
+        let (b, _) = document.extend(a, Vec2::new(3.0, 2.0)).unwrap();
+        let c = document.add_point(Vec2::new(1.001, 2.0));
+        document.extend(c, Vec2::new(1.0, 5.0)).unwrap();
+        let d = document.add_point(Vec2::new(3.0, 2.004));
+        document.extend(d, Vec2::new(3.0, 0.0)).unwrap();
+        assert_eq!(
+            kinds(&check(&document)),
+            [
+                (
+                    IssueKind::CoincidentPoints,
+                    vec![Element::Point(a), Element::Point(c)]
+                ),
+                (
+                    IssueKind::CoincidentPoints,
+                    vec![Element::Point(b), Element::Point(d)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_and_broken_lines() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(-2.5, 0.5));
+        let (b, ab) = document.extend(a, Vec2::new(4.5, 0.5)).unwrap();
+        let c = document.add_point(Vec2::new(3.5, 0.502));
+        let (_, cd) = document.extend(c, Vec2::new(8.0, 0.502)).unwrap();
+        // touching at an end point is fine
+        let (_, be) = document.extend(b, Vec2::new(4.5, 3.0)).unwrap();
+        let duplicate = document.add_line(b, a).unwrap();
+        let isolated = document.add_point(Vec2::new(20.0, 20.0));
+        assert_eq!(
+            kinds(&check(&document)),
+            [
+                (
+                    IssueKind::DuplicateLines,
+                    vec![Element::Line(ab), Element::Line(duplicate)]
+                ),
+                (
+                    IssueKind::OverlappingLines,
+                    vec![Element::Line(ab), Element::Line(cd)]
+                ),
+                (IssueKind::IsolatedPoint, vec![Element::Point(isolated)]),
+            ]
+        );
+        assert!(!kinds(&check(&document))
+            .iter()
+            .any(|(_, elements)| elements.contains(&Element::Line(be))));
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+
+use crate::plan::{document::LabelId, line::LINE_PRIORITY};
+
+// above the walls and points so the text stays readable
+const LABEL_PRIORITY: f32 = LINE_PRIORITY + 2.0;
+const FONT_PATH: &str = "fonts/roboto_bold.ttf";
+
+pub struct LabelPlugin;
+
+impl Plugin for LabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LabelAssets>();
+    }
+}
+
+#[derive(Resource)]
+pub struct LabelAssets {
+    font: Handle<Font>,
+}
+
+impl FromWorld for LabelAssets {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            font: world.resource::<AssetServer>().load(FONT_PATH),
+        }
+    }
+}
+
+// the text is laid out in screen pixels and scaled by the pixel size, so it
+// keeps its size on screen while zooming
+#[derive(Bundle)]
+pub struct LabelBundle {
+    text: Text2dBundle,
+    label: Label,
+}
+
+impl LabelBundle {
+    // colored by the theme
+    pub fn new(id: LabelId, text: &str, font_size: f32, assets: &LabelAssets) -> Self {
+        let style = TextStyle {
+            font: assets.font.clone(),
+            font_size,
+            color: Color::NONE,
+        };
+        Self {
+            text: Text2dBundle {
+                text: Text::from_section(text, style).with_alignment(TextAlignment::Center),
+                transform: Transform::from_translation(Vec2::ZERO.extend(LABEL_PRIORITY)),
+                ..default()
+            },
+            label: Label { id },
+        }
+    }
+
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        let translation = &mut self.text.transform.translation;
+        translation.x = position.x;
+        translation.y = position.y;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.text.transform.scale = Vec3::new(scale, scale, 1.0);
+        self
+    }
+}
+
+#[derive(Component)]
+pub struct Label {
+    pub id: LabelId,
+}
@@ -1,11 +1,11 @@
 use bevy::{
     prelude::*,
     render::{mesh::Indices, render_resource::PrimitiveTopology},
+    sprite::Mesh2dHandle,
 };
 
-use crate::plan::{BASE_PRIORITY, DEFAULT_COLOR};
+use crate::plan::{document::LineId, BASE_PRIORITY};
 
-pub const LINE_WIDTH: f32 = 0.02;
 pub const LINE_PRIORITY: f32 = BASE_PRIORITY + 1.0;
 
 pub struct LinePlugin;
@@ -18,14 +18,17 @@ impl Plugin for LinePlugin {
 
 #[derive(Resource)]
 pub struct LineAssets {
-    material: Handle<ColorMaterial>,
+    pub material: Handle<ColorMaterial>,
+    pub hovered_material: Handle<ColorMaterial>,
 }
 
 impl FromWorld for LineAssets {
     fn from_world(world: &mut World) -> Self {
         let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        // colored by the theme
         Self {
-            material: materials.add(DEFAULT_COLOR.into()),
+            material: materials.add(ColorMaterial::default()),
+            hovered_material: materials.add(ColorMaterial::default()),
         }
     }
 }
@@ -44,23 +47,28 @@ impl LineShape {
             width,
         }
     }
-}
 
-impl From<LineShape> for Mesh {
-    fn from(line: LineShape) -> Self {
-        let extension = LINE_WIDTH / 2.0;
-        let diff = line.point_a - line.point_b;
+    // rewrites the vertices of a mesh built from a line shape, keeping its handle
+    pub fn update(&self, mesh: &mut Mesh) {
+        let extension = self.width / 2.0;
+        let diff = self.point_a - self.point_b;
         let perp_norm = diff.perp().normalize_or_zero();
-        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
         mesh.insert_attribute(
             Mesh::ATTRIBUTE_POSITION,
             vec![
-                (line.point_a - perp_norm * extension).extend(0.0),
-                (line.point_a + perp_norm * extension).extend(0.0),
-                (line.point_b - perp_norm * extension).extend(0.0),
-                (line.point_b + perp_norm * extension).extend(0.0),
+                (self.point_a - perp_norm * extension).extend(0.0),
+                (self.point_a + perp_norm * extension).extend(0.0),
+                (self.point_b - perp_norm * extension).extend(0.0),
+                (self.point_b + perp_norm * extension).extend(0.0),
             ],
         );
+    }
+}
+
+impl From<LineShape> for Mesh {
+    fn from(line: LineShape) -> Self {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        line.update(&mut mesh);
         mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![Vec3::Z; 4]);
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![Vec2::ZERO; 4]);
         mesh.set_indices(Some(Indices::U16(vec![0, 1, 2, 1, 3, 2])));
@@ -75,44 +83,27 @@ pub struct LineBundle {
 }
 
 impl LineBundle {
-    pub fn new(point_a: Entity, point_b: Entity, assets: &LineAssets) -> Self {
+    pub fn new(id: LineId, assets: &LineAssets) -> Self {
         Self {
             material_mesh: ColorMesh2dBundle {
                 material: assets.material.clone(),
                 transform: Transform::from_translation(Vec2::ZERO.extend(LINE_PRIORITY)),
                 ..default()
             },
-            line: Line::new(point_a, point_b),
+            line: Line { id },
         }
     }
+
+    pub fn with_shape(mut self, position: Vec2, mesh: Mesh2dHandle) -> Self {
+        let translation = &mut self.material_mesh.transform.translation;
+        translation.x = position.x;
+        translation.y = position.y;
+        self.material_mesh.mesh = mesh;
+        self
+    }
 }
 
 #[derive(Component)]
 pub struct Line {
-    pub point_a: Entity,
-    pub point_b: Entity,
-}
-
-impl Line {
-    pub fn new(point_a: Entity, point_b: Entity) -> Self {
-        Self { point_a, point_b }
-    }
-
-    pub fn other(&self, point: Entity) -> Option<Entity> {
-        if point == self.point_a {
-            Some(self.point_b)
-        } else if point == self.point_b {
-            Some(self.point_a)
-        } else {
-            None
-        }
-    }
-
-    pub fn replace(&mut self, old: Entity, new: Entity) {
-        if old == self.point_a {
-            self.point_a = new;
-        } else if old == self.point_b {
-            self.point_b = new;
-        }
-    }
+    pub id: LineId,
 }
@@ -1,16 +1,29 @@
+pub mod analysis;
+pub mod document;
+pub mod door;
+pub mod export;
+pub mod integrity;
+pub mod label;
 pub mod line;
 pub mod point;
+pub mod repair;
+pub mod spatial;
+pub mod transform;
+pub mod unit;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-use crate::palette;
+use self::{
+    document::{DoorId, LabelId, LineId, PlanDocument, PointId},
+    door::DoorPlugin,
+    label::LabelPlugin,
+    line::LinePlugin,
+    point::PointPlugin,
+    spatial::SpatialIndex,
+    transform::TransformKind,
+};
 
-use self::{line::LinePlugin, point::PointPlugin};
-
-const BASE_PRIORITY: f32 = 0.0;
-const DEFAULT_COLOR: Color = palette::LIGHT_WHITE;
-const HOVERED_COLOR: Color = palette::LIGHT_GREEN;
-const SELECTED_COLOR: Color = palette::LIGHT_BLUE;
+pub const BASE_PRIORITY: f32 = 0.0;
 
 pub struct PlanPlugin;
 
@@ -18,20 +31,114 @@ impl Plugin for PlanPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(PointPlugin)
             .add_plugin(LinePlugin)
-            .init_resource::<PlanMode>();
+            .add_plugin(DoorPlugin)
+            .add_plugin(LabelPlugin)
+            .init_resource::<PlanDocument>()
+            .init_resource::<PlanView>()
+            .init_resource::<SpatialIndex>()
+            .init_resource::<PlanMode>()
+            .init_resource::<Selection>()
+            .init_resource::<Pivot>()
+            .init_resource::<TrackOrigins>()
+            .init_resource::<HandleSizes>();
     }
 }
 
-#[derive(Resource, Default, Debug)]
+impl Resource for PlanDocument {}
+
+impl Resource for SpatialIndex {}
+
+// maps the stable ids of document elements to the entities that display them
+#[derive(Resource, Default)]
+pub struct PlanView {
+    points: HashMap<PointId, Entity>,
+    lines: HashMap<LineId, Entity>,
+    doors: HashMap<DoorId, Entity>,
+    labels: HashMap<LabelId, Entity>,
+}
+
+impl PlanView {
+    pub fn point_entity(&self, id: PointId) -> Option<Entity> {
+        self.points.get(&id).copied()
+    }
+
+    pub fn line_entity(&self, id: LineId) -> Option<Entity> {
+        self.lines.get(&id).copied()
+    }
+
+    pub fn insert_point(&mut self, id: PointId, entity: Entity) {
+        self.points.insert(id, entity);
+    }
+
+    pub fn remove_point(&mut self, id: PointId) -> Option<Entity> {
+        self.points.remove(&id)
+    }
+
+    pub fn insert_line(&mut self, id: LineId, entity: Entity) {
+        self.lines.insert(id, entity);
+    }
+
+    pub fn remove_line(&mut self, id: LineId) -> Option<Entity> {
+        self.lines.remove(&id)
+    }
+
+    pub fn door_entity(&self, id: DoorId) -> Option<Entity> {
+        self.doors.get(&id).copied()
+    }
+
+    pub fn insert_door(&mut self, id: DoorId, entity: Entity) {
+        self.doors.insert(id, entity);
+    }
+
+    pub fn remove_door(&mut self, id: DoorId) -> Option<Entity> {
+        self.doors.remove(&id)
+    }
+
+    pub fn label_entity(&self, id: LabelId) -> Option<Entity> {
+        self.labels.get(&id).copied()
+    }
+
+    pub fn insert_label(&mut self, id: LabelId, entity: Entity) {
+        self.labels.insert(id, entity);
+    }
+
+    pub fn remove_label(&mut self, id: LabelId) -> Option<Entity> {
+        self.labels.remove(&id)
+    }
+}
+
+// sizes in screen pixels, independent of the camera zoom
+#[derive(Resource)]
+pub struct HandleSizes {
+    pub point_radius: f32,
+    pub line_width: f32,
+    pub door_width: f32,
+    pub label_size: f32,
+    pub hover_radius: f32,
+}
+
+impl Default for HandleSizes {
+    fn default() -> Self {
+        Self {
+            point_radius: 5.0,
+            line_width: 2.0,
+            door_width: 6.0,
+            label_size: 16.0,
+            hover_radius: 8.0,
+        }
+    }
+}
+
+#[derive(Resource, Default, Clone, Copy, Debug)]
 pub enum PlanMode {
     #[default]
     Default,
-    Select(Entity),
-    Track(Entity, TrackMode),
+    Select(PointId),
+    Track(PointId, TrackMode),
 }
 
 impl PlanMode {
-    pub fn selection(&self) -> Option<Entity> {
+    pub fn selection(&self) -> Option<PointId> {
         match *self {
             PlanMode::Select(selection) => Some(selection),
             PlanMode::Track(selection, _) => Some(selection),
@@ -40,8 +147,88 @@ impl PlanMode {
     }
 }
 
+// every selected point, the one in the mode is edited and tracked while the
+// others follow along
+#[derive(Resource, Default)]
+pub struct Selection {
+    pub points: Vec<PointId>,
+}
+
+impl Selection {
+    pub fn contains(&self, id: PointId) -> bool {
+        self.points.contains(&id)
+    }
+
+    // the selected point of the mode always belongs to the selection
+    pub fn sync(&mut self, mode: &PlanMode, document: &PlanDocument) {
+        self.points.retain(|id| document.point(*id).is_some());
+        match mode.selection() {
+            None => self.points.clear(),
+            Some(id) if !self.contains(id) => self.points = vec![id],
+            Some(_) => (),
+        }
+    }
+}
+
+// a transformation tracks the cursor from the start position around the pivot
 #[derive(Clone, Copy, Debug)]
 pub enum TrackMode {
     Place,
     Move(Vec2),
+    Transform(TransformKind, Vec2, Vec2),
+}
+
+// where rotations, scales and mirrors of the selection are centered
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pivot {
+    #[default]
+    Center,
+    Cursor,
+    Point,
+}
+
+impl Pivot {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Pivot::Center => "selection center",
+            Pivot::Cursor => "cursor",
+            Pivot::Point => "selected point",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Pivot::Center => Pivot::Cursor,
+            Pivot::Cursor => Pivot::Point,
+            Pivot::Point => Pivot::Center,
+        }
+    }
+
+    pub fn position(
+        &self,
+        document: &PlanDocument,
+        selection: &Selection,
+        point: PointId,
+        cursor: Option<Vec2>,
+    ) -> Option<Vec2> {
+        match self {
+            Pivot::Center => transform::center(document, &selection.points),
+            Pivot::Cursor => cursor,
+            Pivot::Point => document.position(point),
+        }
+    }
+}
+
+// the selected points where tracking started, a cancel puts them back and a
+// transformation preview is recomputed from these every frame
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct TrackOrigins(Vec<(PointId, Vec2)>);
+
+impl TrackOrigins {
+    pub fn record(&mut self, document: &PlanDocument, points: &[PointId]) {
+        self.0 = points
+            .iter()
+            .filter_map(|id| Some((*id, document.position(*id)?)))
+            .collect();
+    }
 }
@@ -1,8 +1,7 @@
 use bevy::prelude::*;
 
-use crate::plan::{line::LINE_PRIORITY, DEFAULT_COLOR, HOVERED_COLOR, SELECTED_COLOR};
+use crate::plan::{document::PointId, line::LINE_PRIORITY};
 
-pub const POINT_RADIUS: f32 = 0.06;
 pub const POINT_VERTICES: usize = 16;
 const POINT_PRIORITY: f32 = LINE_PRIORITY + 1.0;
 
@@ -25,18 +24,19 @@ pub struct PointAssets {
 impl FromWorld for PointAssets {
     fn from_world(world: &mut World) -> Self {
         world.resource_scope(|world, mut meshes: Mut<Assets<Mesh>>| {
+            // colored by the theme
             let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
             Self {
                 mesh: meshes.add(
                     shape::Circle {
-                        radius: POINT_RADIUS,
+                        radius: 1.0,
                         vertices: POINT_VERTICES,
                     }
                     .into(),
                 ),
-                default_material: materials.add(DEFAULT_COLOR.into()),
-                hovered_material: materials.add(HOVERED_COLOR.into()),
-                selected_material: materials.add(SELECTED_COLOR.into()),
+                default_material: materials.add(ColorMaterial::default()),
+                hovered_material: materials.add(ColorMaterial::default()),
+                selected_material: materials.add(ColorMaterial::default()),
             }
         })
     }
@@ -49,7 +49,7 @@ pub struct PointBundle {
 }
 
 impl PointBundle {
-    pub fn new(lines: Vec<Entity>, assets: &PointAssets) -> Self {
+    pub fn new(id: PointId, assets: &PointAssets) -> Self {
         Self {
             material_mesh: ColorMesh2dBundle {
                 mesh: assets.mesh.clone().into(),
@@ -57,26 +57,24 @@ impl PointBundle {
                 transform: Transform::from_translation(Vec2::ZERO.extend(POINT_PRIORITY)),
                 ..default()
             },
-            point: Point::new(lines),
+            point: Point { id },
         }
     }
 
-    pub fn empty(assets: &PointAssets) -> Self {
-        Self::new(vec![], assets)
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        let translation = &mut self.material_mesh.transform.translation;
+        translation.x = position.x;
+        translation.y = position.y;
+        self
     }
 
-    pub fn from_line(line: Entity, assets: &PointAssets) -> Self {
-        Self::new(vec![line], assets)
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.material_mesh.transform.scale = Vec3::new(radius, radius, 1.0);
+        self
     }
 }
 
 #[derive(Component)]
 pub struct Point {
-    pub lines: Vec<Entity>,
-}
-
-impl Point {
-    pub fn new(lines: Vec<Entity>) -> Self {
-        Self { lines }
-    }
+    pub id: PointId,
 }
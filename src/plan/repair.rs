@@ -0,0 +1,268 @@
+use std::{
+    collections::HashSet,
+    f32::consts::{FRAC_PI_2, PI},
+    fmt,
+};
+
+use glam::Vec2;
+
+use super::{
+    document::{LineId, PlanDocument, PointId},
+    integrity::TOLERANCE,
+    spatial::SpatialIndex,
+};
+
+const STRAIGHTEN_ANGLE: f32 = 2.0;
+const SQUARE_ANGLE: f32 = 3.0;
+// corners closer than this to their target are left alone
+const MIN_ADJUSTMENT: f32 = TOLERANCE / 100.0;
+const CORNER_PASSES: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repair {
+    Weld(PointId, PointId),
+    RemoveSelfLoop(LineId),
+    RemoveZeroLength(LineId),
+    RemoveDuplicate(LineId),
+    Straighten(PointId),
+    Square(PointId),
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Repair::Weld(from, into) => write!(f, "welded point {from} into point {into}"),
+            Repair::RemoveSelfLoop(id) => write!(f, "removed self-loop line {id}"),
+            Repair::RemoveZeroLength(id) => write!(f, "removed zero-length line {id}"),
+            Repair::RemoveDuplicate(id) => write!(f, "removed duplicated line {id}"),
+            Repair::Straighten(id) => write!(f, "straightened the chain at point {id}"),
+            Repair::Square(id) => write!(f, "squared the corner at point {id}"),
+        }
+    }
+}
+
+pub fn repair(document: &mut PlanDocument) -> Vec<Repair> {
+    let mut repairs = Vec::new();
+    weld_points(document, &mut repairs);
+    remove_lines(document, &mut repairs);
+    adjust_corners(document, &mut repairs);
+    repairs
+}
+
+// points are welded into the close point with the lowest id
+fn weld_points(document: &mut PlanDocument, repairs: &mut Vec<Repair>) {
+    let mut index = SpatialIndex::from_document(document);
+    let ids: Vec<PointId> = document.points().map(|(id, _)| id).collect();
+    for into in ids {
+        let Some(position) = document.position(into) else {
+            continue;
+        };
+        let mut close = index.within(position, TOLERANCE);
+        close.sort_unstable();
+        for from in close.into_iter().filter(|from| *from > into) {
+            let Some(other) = document.position(from) else {
+                continue;
+            };
+            if position.distance(other) < TOLERANCE && document.merge_points(from, into) {
+                index.update(from, None);
+                repairs.push(Repair::Weld(from, into));
+            }
+        }
+    }
+}
+
+fn remove_lines(document: &mut PlanDocument, repairs: &mut Vec<Repair>) {
+    let mut pairs = HashSet::new();
+    let lines: Vec<_> = document.lines().map(|(id, line)| (id, *line)).collect();
+    for (id, line) in lines {
+        let repair = if line.point_a == line.point_b {
+            Repair::RemoveSelfLoop(id)
+        } else if document
+            .line_positions(id)
+            .is_some_and(|(a, b)| a.distance(b) < TOLERANCE)
+        {
+            Repair::RemoveZeroLength(id)
+        } else if !pairs.insert((
+            line.point_a.min(line.point_b),
+            line.point_a.max(line.point_b),
+        )) {
+            Repair::RemoveDuplicate(id)
+        } else {
+            continue;
+        };
+        document.remove_line(id);
+        repairs.push(repair);
+    }
+}
+
+// only points joining exactly two lines are moved, moving one changes the
+// corners at its neighbors so the passes go on until the corners settle
+fn adjust_corners(document: &mut PlanDocument, repairs: &mut Vec<Repair>) {
+    let ids: Vec<PointId> = document.points().map(|(id, _)| id).collect();
+    let mut adjusted = HashSet::new();
+    for _ in 0..CORNER_PASSES {
+        let mut moved = false;
+        for id in &ids {
+            let Some((target, repair)) = corner_target(document, *id) else {
+                continue;
+            };
+            document.move_point(*id, target);
+            moved = true;
+            // a corner is reported once however many passes move it
+            if adjusted.insert(*id) {
+                repairs.push(repair);
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+}
+
+fn corner_target(document: &PlanDocument, id: PointId) -> Option<(Vec2, Repair)> {
+    let (position, a, c) = corner(document, id)?;
+    let angle = (a - position).angle_between(c - position).abs();
+    let (target, repair) = if (PI - angle).to_degrees() < STRAIGHTEN_ANGLE {
+        let direction = (c - a).normalize();
+        (
+            a + direction * direction.dot(position - a),
+            Repair::Straighten(id),
+        )
+    } else if (FRAC_PI_2 - angle).abs().to_degrees() < SQUARE_ANGLE {
+        // every point on the circle with diameter a-c sees it at a right angle
+        let center = (a + c) / 2.0;
+        let radius = a.distance(c) / 2.0;
+        (
+            center + (position - center).normalize() * radius,
+            Repair::Square(id),
+        )
+    } else {
+        return None;
+    };
+    (target.distance(position) > MIN_ADJUSTMENT).then_some((target, repair))
+}
+
+fn corner(document: &PlanDocument, id: PointId) -> Option<(Vec2, Vec2, Vec2)> {
+    let point = document.point(id)?;
+    let [line_a, line_c] = point.lines() else {
+        return None;
+    };
+    let a = document.position(document.line(*line_a)?.other(id)?)?;
+    let c = document.position(document.line(*line_c)?.other(id)?)?;
+    let position = point.position();
+    if a.distance(position) < TOLERANCE || c.distance(position) < TOLERANCE {
+        return None;
+    }
+    Some((position, a, c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corner_angle(document: &PlanDocument, id: PointId) -> f32 {
+        let (position, a, c) = corner(document, id).unwrap();
+        (a - position)
+            .angle_between(c - position)
+            .abs()
+            .to_degrees()
+    }
+
+    #[test]
+    fn close_points_are_welded_into_the_first() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(0.999, 0.0));
+        let (b, _) = document.extend(a, Vec2::new(2.0, 0.0)).unwrap();
+        let c = document.add_point(Vec2::new(1.002, 0.0));
+        let (d, _) = document.extend(c, Vec2::new(-1.0, 1.0)).unwrap();
+        assert_eq!(repair(&mut document), [Repair::Weld(c, a)]);
+        assert!(document.point(c).is_none());
+        assert!(document.line_between(a, b).is_some());
+        assert!(document.line_between(a, d).is_some());
+        assert!(repair(&mut document).is_empty());
+    }
+
+    #[test]
+    fn broken_lines_are_removed() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::ZERO);
+        let (b, ab) = document.extend(a, Vec2::new(3.0, 0.0)).unwrap();
+        let self_loop = document.add_line(b, b).unwrap();
+        let duplicate = document.add_line(b, a).unwrap();
+        assert_eq!(
+            repair(&mut document),
+            [
+                Repair::RemoveSelfLoop(self_loop),
+                Repair::RemoveDuplicate(duplicate),
+            ]
+        );
+        assert_eq!(document.lines().map(|(id, _)| id).collect::<Vec<_>>(), [ab]);
+        assert!(repair(&mut document).is_empty());
+    }
+
+    // welding merges the ends of a line this short before it is looked at, so
+    // the lines are checked on their own
+    #[test]
+    fn zero_length_lines_are_removed() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::ZERO);
+        let (b, short) = document.extend(a, Vec2::new(0.001, 0.0)).unwrap();
+        let mut repairs = Vec::new();
+        remove_lines(&mut document, &mut repairs);
+        assert_eq!(repairs, [Repair::RemoveZeroLength(short)]);
+        assert!(document.line(short).is_none());
+        assert_eq!(repair(&mut document), [Repair::Weld(b, a)]);
+    }
+
+    #[test]
+    fn slightly_bent_chains_are_straightened() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::ZERO);
+        let (b, _) = document.extend(a, Vec2::new(1.0, 0.01)).unwrap();
+        let (c, _) = document.extend(b, Vec2::new(2.0, 0.0)).unwrap();
+        assert_eq!(repair(&mut document), [Repair::Straighten(b)]);
+        assert!(document.position(b).unwrap().distance(Vec2::X) < 1e-5);
+        assert_eq!(document.position(a), Some(Vec2::ZERO));
+        assert_eq!(document.position(c), Some(Vec2::new(2.0, 0.0)));
+        assert!(repair(&mut document).is_empty());
+    }
+
+    #[test]
+    fn almost_square_corners_are_squared() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(2.0, 0.0));
+        let (b, _) = document.extend(a, Vec2::ZERO).unwrap();
+        let (c, _) = document.extend(b, Vec2::new(0.05, 1.5)).unwrap();
+        // sharper corners are left as they are
+        let (d, _) = document.extend(c, Vec2::new(1.0, 0.5)).unwrap();
+        assert_eq!(repair(&mut document), [Repair::Square(b)]);
+        assert!((corner_angle(&document, b) - 90.0).abs() < 0.01);
+        assert_eq!(document.position(d), Some(Vec2::new(1.0, 0.5)));
+        assert!(repair(&mut document).is_empty());
+    }
+
+    // squaring one corner of a room skews the next ones, they are reported in
+    // id order and once each however often they are moved
+    #[test]
+    fn corners_of_a_room_settle_together() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::ZERO);
+        let (b, _) = document.extend(a, Vec2::new(4.0, 0.1)).unwrap();
+        let (c, _) = document.extend(b, Vec2::new(4.1, 3.0)).unwrap();
+        let (d, _) = document.extend(c, Vec2::new(0.0, 3.05)).unwrap();
+        document.add_line(d, a).unwrap();
+        assert_eq!(
+            repair(&mut document),
+            [
+                Repair::Square(a),
+                Repair::Square(b),
+                Repair::Square(c),
+                Repair::Square(d),
+            ]
+        );
+        for id in [a, b, c, d] {
+            assert!((corner_angle(&document, id) - 90.0).abs() < 0.01);
+        }
+        assert!(repair(&mut document).is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use super::document::{LineId, PlanDocument, PointId};
+
+// meters, a few times the usual pick radius at a comfortable zoom
+const CELL_SIZE: f32 = 1.0;
+
+type Cell = (i32, i32);
+
+// uniform grid over the point positions and line segments, kept up to date
+// from the document changes
+#[derive(Clone, Debug)]
+pub struct SpatialIndex {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<PointId>>,
+    positions: HashMap<PointId, Vec2>,
+    // a line is listed in every cell its segment passes through
+    line_cells: HashMap<Cell, Vec<LineId>>,
+    segments: HashMap<LineId, (Vec2, Vec2)>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new(CELL_SIZE)
+    }
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            positions: HashMap::new(),
+            line_cells: HashMap::new(),
+            segments: HashMap::new(),
+        }
+    }
+
+    pub fn from_document(document: &PlanDocument) -> Self {
+        let mut index = Self::default();
+        for (id, point) in document.points() {
+            index.update(id, Some(point.position()));
+        }
+        for (id, _) in document.lines() {
+            index.update_line(id, document.line_positions(id));
+        }
+        index
+    }
+
+    // `None` removes the point from the index
+    pub fn update(&mut self, id: PointId, position: Option<Vec2>) {
+        if let Some(old_position) = self.positions.remove(&id) {
+            let cell = self.cell(old_position);
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        if let Some(position) = position {
+            self.positions.insert(id, position);
+            self.cells.entry(self.cell(position)).or_default().push(id);
+        }
+    }
+
+    // `None` removes the line from the index
+    pub fn update_line(&mut self, id: LineId, segment: Option<(Vec2, Vec2)>) {
+        if let Some((a, b)) = self.segments.remove(&id) {
+            for cell in self.segment_cells(a, b) {
+                if let Some(ids) = self.line_cells.get_mut(&cell) {
+                    ids.retain(|other| *other != id);
+                    if ids.is_empty() {
+                        self.line_cells.remove(&cell);
+                    }
+                }
+            }
+        }
+        if let Some((a, b)) = segment {
+            self.segments.insert(id, (a, b));
+            for cell in self.segment_cells(a, b) {
+                self.line_cells.entry(cell).or_default().push(id);
+            }
+        }
+    }
+
+    // points within `radius` ordered by distance, ties are broken by id
+    pub fn within(&self, center: Vec2, radius: f32) -> Vec<PointId> {
+        let mut found: Vec<(f32, PointId)> = self
+            .cells_around(center, radius)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter_map(|id| {
+                let distance = self.positions[id].distance(center);
+                (distance <= radius).then_some((distance, *id))
+            })
+            .collect();
+        found.sort_by(|(a, a_id), (b, b_id)| a.total_cmp(b).then(a_id.cmp(b_id)));
+        found.into_iter().map(|(_, id)| id).collect()
+    }
+
+    // lines passing within `radius` ordered by distance, ties are broken by id
+    pub fn lines_within(&self, center: Vec2, radius: f32) -> Vec<LineId> {
+        let mut ids: Vec<LineId> = self
+            .cells_around(center, radius)
+            .filter_map(|cell| self.line_cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let mut found: Vec<(f32, LineId)> = ids
+            .into_iter()
+            .filter_map(|id| {
+                let (a, b) = self.segments[&id];
+                let distance = segment_distance(center, a, b);
+                (distance <= radius).then_some((distance, id))
+            })
+            .collect();
+        found.sort_by(|(a, a_id), (b, b_id)| a.total_cmp(b).then(a_id.cmp(b_id)));
+        found.into_iter().map(|(_, id)| id).collect()
+    }
+
+    // lines sharing a cell with the segment or one within `distance` of it,
+    // ordered by id, the caller decides which of them really are close
+    pub fn lines_near(&self, a: Vec2, b: Vec2, distance: f32) -> Vec<LineId> {
+        let reach = (distance / self.cell_size).ceil() as i32;
+        let mut ids: Vec<LineId> = self
+            .segment_cells(a, b)
+            .into_iter()
+            .flat_map(|(x, y)| {
+                (x - reach..=x + reach)
+                    .flat_map(move |x| (y - reach..=y + reach).map(move |y| (x, y)))
+            })
+            .filter_map(|cell| self.line_cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    fn cell(&self, position: Vec2) -> Cell {
+        let cell = (position / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    fn cells_around(&self, center: Vec2, radius: f32) -> impl Iterator<Item = Cell> {
+        let (min_x, min_y) = self.cell(center - radius);
+        let (max_x, max_y) = self.cell(center + radius);
+        (min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+
+    // every cell the segment touches, one column of cells at a time
+    fn segment_cells(&self, a: Vec2, b: Vec2) -> Vec<Cell> {
+        let (min, max) = (a.min(b), a.max(b));
+        let (min_x, _) = self.cell(min);
+        let (max_x, _) = self.cell(max);
+        let y_at = |x: f32| match b.x - a.x {
+            dx if dx != 0.0 => (a.y + (b.y - a.y) * (x - a.x) / dx).clamp(min.y, max.y),
+            _ => a.y,
+        };
+        let mut cells = Vec::new();
+        for x in min_x..=max_x {
+            let left = (x as f32 * self.cell_size).max(min.x);
+            let right = ((x + 1) as f32 * self.cell_size).min(max.x);
+            let (low, high) = match a.x == b.x {
+                true => (min.y, max.y),
+                false => (y_at(left).min(y_at(right)), y_at(left).max(y_at(right))),
+            };
+            let (_, min_y) = self.cell(Vec2::new(left, low));
+            let (_, max_y) = self.cell(Vec2::new(left, high));
+            cells.extend((min_y..=max_y).map(|y| (x, y)));
+        }
+        cells
+    }
+}
+
+pub fn segment_distance(position: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let direction = b - a;
+    let t = match direction.length_squared() {
+        length if length > 0.0 => ((position - a).dot(direction) / length).clamp(0.0, 1.0),
+        _ => 0.0,
+    };
+    position.distance(a + direction * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_are_found_nearest_first() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(0.5, 0.5));
+        let b = document.add_point(Vec2::new(1.2, 0.5));
+        let c = document.add_point(Vec2::new(5.0, 5.0));
+        let mut index = SpatialIndex::from_document(&document);
+        assert_eq!(index.within(Vec2::new(1.0, 0.5), 0.6), [b, a]);
+        index.update(b, None);
+        index.update(c, Some(Vec2::new(1.0, 0.6)));
+        assert_eq!(index.within(Vec2::new(1.0, 0.5), 0.6), [c, a]);
+    }
+
+    #[test]
+    fn lines_are_found_along_their_whole_length() {
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(-3.5, -2.2));
+        let (b, diagonal) = document.extend(a, Vec2::new(6.3, 4.1)).unwrap();
+        let (_, vertical) = document.extend(b, Vec2::new(6.3, -8.0)).unwrap();
+        let mut index = SpatialIndex::from_document(&document);
+        // every point on the diagonal has the line within reach
+        for step in 0..=100 {
+            let position = Vec2::new(-3.5, -2.2).lerp(Vec2::new(6.3, 4.1), step as f32 / 100.0);
+            assert!(index.lines_within(position, 0.01).contains(&diagonal));
+        }
+        assert_eq!(
+            index.lines_within(Vec2::new(6.0, 4.0), 0.5),
+            [diagonal, vertical]
+        );
+        assert_eq!(index.lines_within(Vec2::new(6.4, 0.0), 0.2), [vertical]);
+        assert!(index.lines_within(Vec2::new(0.0, 3.0), 0.5).is_empty());
+        index.update_line(vertical, None);
+        assert!(index.lines_within(Vec2::new(6.4, 0.0), 0.2).is_empty());
+        assert_eq!(
+            index.lines_near(Vec2::new(6.3, 0.0), Vec2::new(6.3, -8.0), 0.0),
+            []
+        );
+        assert_eq!(
+            index.lines_near(Vec2::new(-4.0, -2.0), Vec2::new(-3.6, -2.0), 0.1),
+            [diagonal]
+        );
+    }
+}
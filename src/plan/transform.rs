@@ -0,0 +1,327 @@
+use std::fmt;
+
+use glam::Vec2;
+
+use super::document::{PlanDocument, PointId};
+
+// coarse interactive rotations and mirrors turn in steps of this many degrees
+const ANGLE_STEP: f32 = 15.0;
+
+// closer to the pivot there is no direction to turn or distance to scale by
+const MIN_REFERENCE: f32 = 1e-3;
+
+// applied to the selected points as a whole
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transformation {
+    // counterclockwise, in radians
+    Rotate { pivot: Vec2, angle: f32 },
+    Scale { pivot: Vec2, factor: Vec2 },
+    // across the axis through the origin in the direction
+    Mirror { origin: Vec2, direction: Vec2 },
+}
+
+impl Transformation {
+    pub fn apply(&self, position: Vec2) -> Vec2 {
+        match *self {
+            Transformation::Rotate { pivot, angle } => {
+                pivot + Vec2::from_angle(angle).rotate(position - pivot)
+            }
+            Transformation::Scale { pivot, factor } => pivot + (position - pivot) * factor,
+            Transformation::Mirror { origin, direction } => {
+                let direction = direction.normalize_or_zero();
+                let offset = position - origin;
+                origin + direction * 2.0 * offset.dot(direction) - offset
+            }
+        }
+    }
+
+    // a zero factor or axis would collapse the selection into a line or point
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Transformation::Rotate { pivot, angle } => pivot.is_finite() && angle.is_finite(),
+            Transformation::Scale { pivot, factor } => {
+                pivot.is_finite() && factor.is_finite() && factor.x != 0.0 && factor.y != 0.0
+            }
+            Transformation::Mirror { origin, direction } => {
+                origin.is_finite() && direction.is_finite() && direction != Vec2::ZERO
+            }
+        }
+    }
+}
+
+impl fmt::Display for Transformation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Transformation::Rotate { angle, .. } => write!(f, "Rotate {:.1}°", angle.to_degrees()),
+            Transformation::Scale { factor, .. } if factor.x == factor.y => {
+                write!(f, "Scale {:.2}", factor.x)
+            }
+            Transformation::Scale { factor, .. } => {
+                write!(f, "Scale {:.2} × {:.2}", factor.x, factor.y)
+            }
+            Transformation::Mirror { direction, .. } => {
+                let angle = direction.y.atan2(direction.x).to_degrees();
+                write!(f, "Mirror at {:.1}°", angle.rem_euclid(180.0))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransformKind {
+    Rotate,
+    Scale,
+    Stretch,
+    Mirror,
+}
+
+impl TransformKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransformKind::Rotate => "Rotate",
+            TransformKind::Scale => "Scale",
+            TransformKind::Stretch => "Stretch",
+            TransformKind::Mirror => "Mirror",
+        }
+    }
+
+    // what moving the cursor from the start to the position does around the
+    // pivot, a mirror takes the line it is over as the axis
+    pub fn transformation(
+        &self,
+        pivot: Vec2,
+        start: Vec2,
+        position: Vec2,
+        line: Option<(Vec2, Vec2)>,
+        snap: bool,
+    ) -> Option<Transformation> {
+        let (from, to) = (start - pivot, position - pivot);
+        let snap_angle = |angle: f32| match snap {
+            true => (angle / ANGLE_STEP.to_radians()).round() * ANGLE_STEP.to_radians(),
+            false => angle,
+        };
+        let transformation = match self {
+            TransformKind::Rotate if to.length() < MIN_REFERENCE => return None,
+            TransformKind::Rotate => Transformation::Rotate {
+                pivot,
+                angle: snap_angle(from.angle_between(to)),
+            },
+            TransformKind::Scale => Transformation::Scale {
+                pivot,
+                factor: Vec2::splat(to.length() / from.length()),
+            },
+            // an axis the cursor did not move along keeps its size
+            TransformKind::Stretch => Transformation::Scale {
+                pivot,
+                factor: Vec2::select(
+                    from.abs().cmpge(Vec2::splat(MIN_REFERENCE)),
+                    to / from,
+                    Vec2::ONE,
+                ),
+            },
+            TransformKind::Mirror => match line {
+                Some((a, b)) => Transformation::Mirror {
+                    origin: a,
+                    direction: b - a,
+                },
+                None if to.length() < MIN_REFERENCE => return None,
+                None => Transformation::Mirror {
+                    origin: pivot,
+                    direction: Vec2::from_angle(snap_angle(to.y.atan2(to.x))),
+                },
+            },
+        };
+        transformation.is_valid().then_some(transformation)
+    }
+}
+
+// the cursor can start on the pivot, the selection center or a unit offset
+// then stands in as the reference the cursor is compared to
+pub fn reference(pivot: Vec2, candidates: impl IntoIterator<Item = Vec2>) -> Vec2 {
+    candidates
+        .into_iter()
+        .find(|candidate| candidate.distance(pivot) >= MIN_REFERENCE)
+        .unwrap_or(pivot + Vec2::X)
+}
+
+// the middle of the bounding box
+pub fn center(document: &PlanDocument, points: &[PointId]) -> Option<Vec2> {
+    let mut positions = points.iter().filter_map(|id| document.position(*id));
+    let first = positions.next()?;
+    let (min, max) = positions.fold((first, first), |(min, max), position| {
+        (min.min(position), max.max(position))
+    });
+    Some((min + max) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        a.distance(b) < 1e-5
+    }
+
+    #[test]
+    fn rotations_snap_to_steps() {
+        let pivot = Vec2::new(1.0, 1.0);
+        let start = pivot + Vec2::X;
+        let position = pivot + Vec2::from_angle(37.0_f32.to_radians()) * 3.0;
+        let rotation =
+            |snap| match TransformKind::Rotate.transformation(pivot, start, position, None, snap) {
+                Some(Transformation::Rotate { angle, .. }) => angle.to_degrees(),
+                other => panic!("expected a rotation, got {other:?}"),
+            };
+        assert!((rotation(false) - 37.0).abs() < 1e-3);
+        assert!((rotation(true) - 30.0).abs() < 1e-3);
+        // the cursor on the pivot has no direction
+        assert_eq!(
+            TransformKind::Rotate.transformation(pivot, start, pivot, None, false),
+            None
+        );
+    }
+
+    #[test]
+    fn scales_follow_the_distance_to_the_pivot() {
+        let scale = TransformKind::Scale.transformation(
+            Vec2::ZERO,
+            Vec2::new(2.0, 0.0),
+            Vec2::new(0.0, 3.0),
+            None,
+            true,
+        );
+        assert_eq!(
+            scale,
+            Some(Transformation::Scale {
+                pivot: Vec2::ZERO,
+                factor: Vec2::splat(1.5),
+            })
+        );
+        // back on the pivot would collapse the selection
+        let collapsed =
+            TransformKind::Scale.transformation(Vec2::ZERO, Vec2::X, Vec2::ZERO, None, false);
+        assert_eq!(collapsed, None);
+    }
+
+    #[test]
+    fn stretches_keep_axes_the_cursor_did_not_move_along() {
+        let pivot = Vec2::new(1.0, 1.0);
+        let stretch = |start: Vec2, position: Vec2| match TransformKind::Stretch
+            .transformation(pivot, start, position, None, false)
+        {
+            Some(Transformation::Scale { factor, .. }) => factor,
+            other => panic!("expected a scale, got {other:?}"),
+        };
+        assert_eq!(
+            stretch(Vec2::new(3.0, 1.0), Vec2::new(4.0, 5.0)),
+            Vec2::new(1.5, 1.0)
+        );
+        assert_eq!(
+            stretch(Vec2::new(1.0, 2.0), Vec2::new(7.0, -1.0)),
+            Vec2::new(1.0, -2.0)
+        );
+        assert_eq!(
+            stretch(Vec2::new(3.0, 3.0), Vec2::new(4.0, 2.0)),
+            Vec2::new(1.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn mirrors_use_the_line_under_the_cursor() {
+        let line = (Vec2::new(0.0, 2.0), Vec2::new(4.0, 2.0));
+        let mirror = TransformKind::Mirror
+            .transformation(Vec2::ZERO, Vec2::X, Vec2::new(9.0, 9.0), Some(line), true)
+            .unwrap();
+        assert!(close(
+            mirror.apply(Vec2::new(1.0, 5.0)),
+            Vec2::new(1.0, -1.0)
+        ));
+        // without a line the axis points at the cursor, in steps when snapping
+        let mirror = TransformKind::Mirror
+            .transformation(Vec2::ZERO, Vec2::X, Vec2::new(1.0, 1.1), None, true)
+            .unwrap();
+        assert!(close(
+            mirror.apply(Vec2::new(2.0, 0.0)),
+            Vec2::new(0.0, 2.0)
+        ));
+        assert_eq!(mirror.to_string(), "Mirror at 45.0°");
+        let on_pivot =
+            TransformKind::Mirror.transformation(Vec2::ZERO, Vec2::X, Vec2::ZERO, None, false);
+        assert_eq!(on_pivot, None);
+    }
+
+    #[test]
+    fn transformations_apply_around_their_pivot() {
+        let pivot = Vec2::new(1.0, 1.0);
+        let rotate = Transformation::Rotate {
+            pivot,
+            angle: 90.0_f32.to_radians(),
+        };
+        assert!(close(
+            rotate.apply(Vec2::new(2.0, 1.0)),
+            Vec2::new(1.0, 2.0)
+        ));
+        let scale = Transformation::Scale {
+            pivot,
+            factor: Vec2::new(2.0, -1.0),
+        };
+        assert_eq!(scale.apply(Vec2::new(2.0, 3.0)), Vec2::new(3.0, -1.0));
+        let mirror = Transformation::Mirror {
+            origin: pivot,
+            direction: Vec2::new(0.0, 5.0),
+        };
+        assert!(close(
+            mirror.apply(Vec2::new(3.0, 4.0)),
+            Vec2::new(-1.0, 4.0)
+        ));
+        assert_eq!(scale.to_string(), "Scale 2.00 × -1.00");
+    }
+
+    #[test]
+    fn collapsing_transformations_are_invalid() {
+        let valid = Transformation::Scale {
+            pivot: Vec2::ZERO,
+            factor: Vec2::new(-1.0, 2.0),
+        };
+        assert!(valid.is_valid());
+        for factor in [Vec2::new(0.0, 1.0), Vec2::new(1.0, 0.0), Vec2::ZERO] {
+            let scale = Transformation::Scale {
+                pivot: Vec2::ZERO,
+                factor,
+            };
+            assert!(!scale.is_valid());
+        }
+        let mirror = Transformation::Mirror {
+            origin: Vec2::ZERO,
+            direction: Vec2::ZERO,
+        };
+        assert!(!mirror.is_valid());
+        let rotate = Transformation::Rotate {
+            pivot: Vec2::new(f32::NAN, 0.0),
+            angle: 1.0,
+        };
+        assert!(!rotate.is_valid());
+    }
+
+    #[test]
+    fn references_and_centers() {
+        let pivot = Vec2::new(2.0, 2.0);
+        assert_eq!(reference(pivot, [Vec2::new(2.0, 3.0)]), Vec2::new(2.0, 3.0));
+        // candidates on the pivot are skipped
+        assert_eq!(
+            reference(pivot, [pivot, Vec2::new(0.0, 2.0)]),
+            Vec2::new(0.0, 2.0)
+        );
+        assert_eq!(reference(pivot, [pivot]), Vec2::new(3.0, 2.0));
+
+        let mut document = PlanDocument::default();
+        let a = document.add_point(Vec2::new(-1.0, 0.0));
+        let b = document.add_point(Vec2::new(3.0, 1.0));
+        let c = document.add_point(Vec2::new(0.0, 4.0));
+        assert_eq!(center(&document, &[a, b, c]), Some(Vec2::new(1.0, 2.0)));
+        assert_eq!(center(&document, &[b]), Some(Vec2::new(3.0, 1.0)));
+        document.remove_point(b);
+        assert_eq!(center(&document, &[b]), None);
+        assert_eq!(center(&document, &[a, b]), Some(Vec2::new(-1.0, 0.0)));
+    }
+}
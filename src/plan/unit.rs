@@ -0,0 +1,87 @@
+pub const METERS_PER_INCH: f32 = 0.0254;
+pub const INCHES_PER_FOOT: u32 = 12;
+pub const MAX_DECIMALS: f32 = 4.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Unit {
+    Meters,
+    Centimeters,
+    Millimeters,
+    FeetInches,
+}
+
+impl Unit {
+    pub const ALL: [Unit; 4] = [
+        Unit::Meters,
+        Unit::Centimeters,
+        Unit::Millimeters,
+        Unit::FeetInches,
+    ];
+
+    pub fn parse(symbol: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|unit| unit.symbol() == symbol)
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Meters => "m",
+            Unit::Centimeters => "cm",
+            Unit::Millimeters => "mm",
+            Unit::FeetInches => "ft-in",
+        }
+    }
+
+    pub fn is_imperial(&self) -> bool {
+        matches!(self, Unit::FeetInches)
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Unit::Meters => Unit::Centimeters,
+            Unit::Centimeters => Unit::Millimeters,
+            Unit::Millimeters => Unit::FeetInches,
+            Unit::FeetInches => Unit::Meters,
+        }
+    }
+
+    // meters per unit, bare imperial numbers are read as inches
+    pub fn factor(&self) -> f32 {
+        match self {
+            Unit::Meters => 1.0,
+            Unit::Centimeters => 0.01,
+            Unit::Millimeters => 0.001,
+            Unit::FeetInches => METERS_PER_INCH,
+        }
+    }
+
+    // a bare number for files read by other programs, imperial lengths in inches
+    pub fn format_number(&self, meters: f32) -> String {
+        let number = format!("{:.*}", MAX_DECIMALS as usize, meters / self.factor());
+        match number.trim_end_matches('0').trim_end_matches('.') {
+            "-0" => "0".to_string(),
+            number => number.to_string(),
+        }
+    }
+
+    pub fn number_symbol(&self) -> &'static str {
+        match self.is_imperial() {
+            true => "in",
+            false => self.symbol(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_for_export() {
+        assert_eq!(Unit::Centimeters.format_number(1.5), "150");
+        assert_eq!(Unit::Centimeters.format_number(0.0125), "1.25");
+        assert_eq!(Unit::Centimeters.format_number(-0.0000001), "0");
+        assert_eq!(Unit::FeetInches.format_number(0.0254 * 6.5), "6.5");
+        assert_eq!(Unit::FeetInches.number_symbol(), "in");
+        assert_eq!(Unit::Millimeters.number_symbol(), "mm");
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs;
+
+use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
+
+use crate::{
+    binding::Command,
+    notification::Notification,
+    palette,
+    plan::{door::DoorAssets, line::LineAssets, point::PointAssets},
+    AppSet,
+};
+
+const THEME_PATH: &str = "assets/config/theme.cfg";
+const BUILT_IN: [&str; 4] = ["dark", "light", "high-contrast", "color-blind"];
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>().add_systems(
+            (switch_theme, apply_theme)
+                .chain()
+                .in_set(AppSet::Consolidation),
+        );
+    }
+}
+
+// colors by role, everything drawn on screen takes its color from here
+#[derive(Resource, Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub wall: Color,
+    pub door: Color,
+    pub point: Color,
+    pub hover: Color,
+    pub selection: Color,
+    pub grid: Color,
+    pub text: Color,
+    pub panel: Color,
+    pub panel_hover: Color,
+    pub warning: Color,
+    pub error: Color,
+}
+
+impl FromWorld for Theme {
+    fn from_world(_: &mut World) -> Self {
+        let (theme, warnings) = Self::load();
+        for warning in warnings {
+            warn!("{warning}");
+        }
+        theme
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            background: palette::DARK_BLACK,
+            wall: palette::LIGHT_WHITE,
+            door: palette::LIGHT_YELLOW,
+            point: palette::LIGHT_WHITE,
+            hover: palette::LIGHT_GREEN,
+            selection: palette::LIGHT_BLUE,
+            grid: palette::LIGHT_BLACK.with_a(0.3),
+            text: palette::LIGHT_WHITE,
+            panel: palette::DARK_BLACK,
+            panel_hover: palette::LIGHT_BLACK,
+            warning: palette::DARK_ORANGE,
+            error: palette::DARK_RED,
+        }
+    }
+
+    // for printing and screen sharing
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            background: Color::WHITE,
+            wall: palette::DARK_BLACK,
+            door: palette::DARK_PURPLE,
+            point: palette::DARK_BLACK,
+            hover: palette::DARK_GREEN,
+            selection: palette::DARK_BLUE,
+            grid: palette::DARK_WHITE.with_a(0.5),
+            text: palette::DARK_BLACK,
+            panel: palette::LIGHT_WHITE,
+            panel_hover: palette::DARK_WHITE,
+            warning: palette::LIGHT_ORANGE,
+            error: palette::LIGHT_RED,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            background: Color::BLACK,
+            wall: Color::WHITE,
+            door: Color::ORANGE,
+            point: Color::WHITE,
+            hover: Color::YELLOW,
+            selection: Color::CYAN,
+            grid: Color::rgb(0.35, 0.35, 0.35),
+            text: Color::WHITE,
+            panel: Color::BLACK,
+            panel_hover: Color::rgb(0.3, 0.3, 0.3),
+            warning: Color::rgb(0.7, 0.4, 0.0),
+            error: Color::rgb(0.75, 0.0, 0.0),
+        }
+    }
+
+    // doors, hover and selection from the Okabe-Ito palette stay apart for all
+    // common color deficiencies
+    pub fn color_blind() -> Self {
+        Self {
+            name: "color-blind".to_string(),
+            door: Color::rgb_u8(0x00, 0x9e, 0x73),
+            hover: Color::rgb_u8(0xe6, 0x9f, 0x00),
+            selection: Color::rgb_u8(0x56, 0xb4, 0xe9),
+            warning: Color::rgb_u8(0xcc, 0x79, 0xa7),
+            error: Color::rgb_u8(0xd5, 0x5e, 0x00),
+            ..Self::dark()
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "color-blind" => Some(Self::color_blind()),
+            _ => None,
+        }
+    }
+
+    fn next(&self) -> Self {
+        let index = BUILT_IN.iter().position(|name| *name == self.name);
+        let next = index.map_or(0, |index| (index + 1) % BUILT_IN.len());
+        Self::built_in(BUILT_IN[next]).unwrap()
+    }
+
+    // the warnings describe an unreadable file or invalid lines, which are skipped
+    pub fn load() -> (Self, Vec<String>) {
+        match fs::read_to_string(THEME_PATH) {
+            Ok(source) => Self::parse(&source),
+            Err(error) => (
+                Self::dark(),
+                vec![format!(
+                    "Using the dark theme, could not read {THEME_PATH}: {error}"
+                )],
+            ),
+        }
+    }
+
+    // `base = <built-in theme>` followed by `<role> = #rrggbb[aa]` overrides
+    pub fn parse(source: &str) -> (Self, Vec<String>) {
+        let mut theme = Self::dark();
+        let mut warnings = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            // colors start with `#` too, so trailing comments need spaces around it
+            let line = line.split(" # ").next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Err(error) = theme.parse_line(line) {
+                warnings.push(format!(
+                    "Invalid theme line in {THEME_PATH}, line {}: {error}",
+                    index + 1
+                ));
+            }
+        }
+        (theme, warnings)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let (role, value) = line
+            .split_once('=')
+            .map(|(role, value)| (role.trim(), value.trim()))
+            .ok_or("expected `role = value`")?;
+        if role == "base" {
+            *self = Self::built_in(value).ok_or(format!("unknown theme `{value}`"))?;
+            return Ok(());
+        }
+        let color = Color::hex(value).map_err(|_| format!("invalid color `{value}`"))?;
+        let field = match role {
+            "background" => &mut self.background,
+            "wall" => &mut self.wall,
+            "door" => &mut self.door,
+            "point" => &mut self.point,
+            "hover" => &mut self.hover,
+            "selection" => &mut self.selection,
+            "grid" => &mut self.grid,
+            "text" => &mut self.text,
+            "panel" => &mut self.panel,
+            "panel-hover" => &mut self.panel_hover,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            _ => return Err(format!("unknown role `{role}`")),
+        };
+        *field = color;
+        self.name = "custom".to_string();
+        Ok(())
+    }
+}
+
+fn switch_theme(
+    mut command_events: EventReader<Command>,
+    mut theme: ResMut<Theme>,
+    mut notifications: EventWriter<Notification>,
+) {
+    for command in command_events.iter() {
+        match command {
+            Command::CycleTheme => {
+                *theme = theme.next();
+                notifications.send(Notification::info(format!("Theme: {}", theme.name)));
+            }
+            Command::ReloadTheme => {
+                let (loaded, warnings) = Theme::load();
+                *theme = loaded;
+                notifications.send_batch(warnings.into_iter().map(Notification::warning));
+                notifications.send(Notification::info(format!("Theme: {}", theme.name)));
+            }
+            _ => (),
+        }
+    }
+}
+
+// the plan shares a few materials, changing them recolors every element at once
+fn apply_theme(
+    theme: Res<Theme>,
+    point_assets: Res<PointAssets>,
+    line_assets: Res<LineAssets>,
+    door_assets: Res<DoorAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut camera_query: Query<&mut Camera2d>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    let colors = [
+        (&point_assets.default_material, theme.point),
+        (&point_assets.hovered_material, theme.hover),
+        (&point_assets.selected_material, theme.selection),
+        (&line_assets.material, theme.wall),
+        (&line_assets.hovered_material, theme.hover),
+        (&door_assets.material, theme.door),
+        (&door_assets.hovered_material, theme.hover),
+    ];
+    for (handle, color) in colors {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = color;
+        }
+    }
+    for mut camera in &mut camera_query {
+        camera.clear_color = ClearColorConfig::Custom(theme.background);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_themes_cycle() {
+        for name in BUILT_IN {
+            let theme = Theme::built_in(name).unwrap();
+            assert_eq!(theme.name, name);
+            assert!(BUILT_IN.contains(&theme.next().name.as_str()));
+        }
+        assert!(Theme::built_in("custom").is_none());
+    }
+
+    #[test]
+    fn overrides_apply_on_top_of_the_base() {
+        let source = "# comment\nbase = light\nwall = #ff0000 # red walls\npanel-hover=#00ff0080\n";
+        let (theme, warnings) = Theme::parse(source);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(theme.name, "custom");
+        assert_eq!(theme.wall, Color::rgb(1.0, 0.0, 0.0));
+        assert_eq!(theme.panel_hover, Color::rgba(0.0, 1.0, 0.0, 128.0 / 255.0));
+        assert_eq!(theme.background, Theme::light().background);
+    }
+
+    #[test]
+    fn a_base_alone_keeps_the_name() {
+        let (theme, warnings) = Theme::parse("base = high-contrast");
+        assert!(warnings.is_empty());
+        assert_eq!(theme.name, "high-contrast");
+    }
+
+    #[test]
+    fn invalid_lines_are_skipped() {
+        let source = "base = sepia\nwall = red\nfloor = #ffffff\nhover #ffffff\ntext = #123456\n";
+        let (theme, warnings) = Theme::parse(source);
+        let errors: Vec<&str> = warnings
+            .iter()
+            .map(|warning| warning.split_once(": ").unwrap().1)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "unknown theme `sepia`",
+                "invalid color `red`",
+                "unknown role `floor`",
+                "expected `role = value`",
+            ]
+        );
+        assert!(warnings[0].ends_with("line 1: unknown theme `sepia`"));
+        assert_eq!(theme.text, Color::hex("123456").unwrap());
+        assert_eq!(theme.wall, Theme::dark().wall);
+    }
+}
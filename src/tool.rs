@@ -0,0 +1,510 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::{Action, ActionQueue},
+    binding::Command,
+    camera::{CameraPan, PixelSize},
+    console::Console,
+    input::{Cursor, CursorMode, Hover, InputFocus},
+    notification::Notification,
+    plan::{
+        analysis,
+        document::{PlanDocument, PointId},
+        transform::TransformKind,
+        Pivot, PlanMode, TrackMode,
+    },
+    units::UnitSystem,
+    AppSet,
+};
+
+// in screen pixels, shorter movements while pressing a point are clicks
+const DRAG_THRESHOLD: f32 = 4.0;
+// meters, a common interior door
+const DOOR_WIDTH: f32 = 0.9;
+
+pub struct ToolPlugin;
+
+impl Plugin for ToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tool>()
+            .init_resource::<Measurement>()
+            .init_resource::<CursorHint>()
+            .add_systems((switch_tool, switch_pivot, drag_point).in_set(AppSet::Binding))
+            .add_systems((measure_distance, inspect_room, place_label).in_set(AppSet::Action))
+            .add_system(update_cursor_hint.in_set(AppSet::Consolidation));
+    }
+}
+
+// every tool has its own binding table and decides what the commands do
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tool {
+    #[default]
+    Select,
+    Wall,
+    Measure,
+    Room,
+    Door,
+    Text,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 6] = [
+        Tool::Select,
+        Tool::Wall,
+        Tool::Measure,
+        Tool::Room,
+        Tool::Door,
+        Tool::Text,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Tool::Select => "Select",
+            Tool::Wall => "Wall",
+            Tool::Measure => "Measure",
+            Tool::Room => "Room",
+            Tool::Door => "Door",
+            Tool::Text => "Text",
+        }
+    }
+
+    pub fn command(&self) -> Command {
+        match self {
+            Tool::Select => Command::SelectTool,
+            Tool::Wall => Command::WallTool,
+            Tool::Measure => Command::MeasureTool,
+            Tool::Room => Command::RoomTool,
+            Tool::Door => Command::DoorTool,
+            Tool::Text => Command::TextTool,
+        }
+    }
+
+    fn from_command(command: Command) -> Option<Self> {
+        Self::ALL.into_iter().find(|tool| tool.command() == command)
+    }
+
+    pub fn actions(
+        &self,
+        command: Command,
+        mode: &PlanMode,
+        hover: &Hover,
+        position: Option<Vec2>,
+    ) -> Vec<Action> {
+        match self {
+            Tool::Select => select_actions(command, mode, hover, position),
+            Tool::Wall => wall_actions(command, mode, hover, position),
+            Tool::Door => door_actions(command, hover, position),
+            // the text is typed into the console, see `place_label`
+            Tool::Text => match (command, hover.label) {
+                (Command::Delete, Some(label)) => vec![Action::DeleteLabel(label)],
+                _ => vec![],
+            },
+            // these only read the plan, see `measure_distance` and `inspect_room`
+            Tool::Measure | Tool::Room => vec![],
+        }
+    }
+}
+
+fn select_actions(
+    command: Command,
+    mode: &PlanMode,
+    hover: &Hover,
+    position: Option<Vec2>,
+) -> Vec<Action> {
+    match (command, *mode) {
+        (Command::Create, PlanMode::Default) => vec![Action::Create],
+        (Command::Split, PlanMode::Default) => match (hover.line, position) {
+            (Some(line), Some(position)) => vec![Action::Split(line, position)],
+            _ => vec![],
+        },
+        (Command::Paste, PlanMode::Default | PlanMode::Select(_)) => {
+            position.map(Action::Paste).into_iter().collect()
+        }
+        (Command::ToggleSelection, PlanMode::Default | PlanMode::Select(_)) => {
+            hover.point.map(Action::ToggleSelect).into_iter().collect()
+        }
+        // the smallest element under the cursor goes
+        (Command::Delete, PlanMode::Default) => {
+            match (hover.point, hover.label, hover.door, hover.line) {
+                (Some(_), _, _, _) => vec![],
+                (None, Some(label), _, _) => vec![Action::DeleteLabel(label)],
+                (None, None, Some(door), _) => vec![Action::DeleteDoor(door)],
+                (None, None, None, Some(line)) => vec![Action::DeleteLine(line)],
+                (None, None, None, None) => vec![],
+            }
+        }
+        (Command::Click, PlanMode::Default) => {
+            hover.point.map(Action::Select).into_iter().collect()
+        }
+        (Command::Track, PlanMode::Select(selection)) => vec![Action::Track(selection)],
+        (Command::Extend, PlanMode::Select(selection)) => vec![Action::Extend(selection)],
+        (Command::Delete, PlanMode::Select(selection) | PlanMode::Track(selection, _)) => {
+            vec![Action::Delete(selection)]
+        }
+        (Command::Copy, PlanMode::Select(_)) => vec![Action::Copy],
+        (Command::Cut, PlanMode::Select(_)) => vec![Action::Cut],
+        (Command::Duplicate, PlanMode::Select(_)) => {
+            position.map(Action::Duplicate).into_iter().collect()
+        }
+        (Command::Rotate, PlanMode::Select(_)) => track_transform(TransformKind::Rotate, position),
+        (Command::Scale, PlanMode::Select(_)) => track_transform(TransformKind::Scale, position),
+        (Command::Stretch, PlanMode::Select(_)) => {
+            track_transform(TransformKind::Stretch, position)
+        }
+        (Command::Mirror, PlanMode::Select(_)) => track_transform(TransformKind::Mirror, position),
+        (Command::Dissolve, PlanMode::Select(selection)) => vec![Action::Dissolve(selection)],
+        // the inspector opens on its own, see `open_properties`
+        (Command::Properties, PlanMode::Select(selection)) => vec![Action::Select(selection)],
+        (Command::Unselect, PlanMode::Select(_)) => vec![Action::Unselect],
+        (Command::Click, PlanMode::Select(selection)) => match hover.point {
+            Some(_) if hover.candidates.contains(&selection) => hover
+                .cycle(selection)
+                .map(Action::Select)
+                .into_iter()
+                .collect(),
+            Some(hover) => vec![Action::Select(hover)],
+            None => vec![Action::Unselect],
+        },
+        (Command::Cancel, PlanMode::Track(selection, TrackMode::Move(_))) => {
+            vec![Action::Restore(selection)]
+        }
+        (Command::Cancel, PlanMode::Track(selection, TrackMode::Place)) => {
+            vec![Action::Delete(selection)]
+        }
+        (Command::Cancel, PlanMode::Track(selection, TrackMode::Transform(..))) => {
+            vec![Action::Restore(selection)]
+        }
+        // the preview is already in place, hovered points are not merged
+        (Command::Click, PlanMode::Track(selection, TrackMode::Transform(..))) => {
+            vec![Action::Select(selection)]
+        }
+        (Command::Click, PlanMode::Track(selection, _)) => match hover.point {
+            Some(hover) => vec![Action::Merge(selection, hover)],
+            None => vec![Action::Select(selection)],
+        },
+        _ => vec![],
+    }
+}
+
+fn track_transform(kind: TransformKind, position: Option<Vec2>) -> Vec<Action> {
+    position
+        .map(|position| Action::TrackTransform(kind, position))
+        .into_iter()
+        .collect()
+}
+
+// every click fixes the end of the current wall and starts the next one,
+// clicking an existing point joins it and ends the chain
+fn wall_actions(
+    command: Command,
+    mode: &PlanMode,
+    hover: &Hover,
+    position: Option<Vec2>,
+) -> Vec<Action> {
+    match (command, *mode) {
+        (Command::Click, PlanMode::Default | PlanMode::Select(_)) => match hover.point {
+            Some(hover) => vec![Action::Extend(hover)],
+            None => position.map(Action::Start).into_iter().collect(),
+        },
+        (Command::Click, PlanMode::Track(selection, _)) => match hover.point {
+            Some(hover) => vec![Action::Merge(selection, hover), Action::Unselect],
+            None => vec![Action::Extend(selection)],
+        },
+        (Command::Cancel, PlanMode::Track(selection, TrackMode::Place)) => {
+            vec![Action::Discard(selection)]
+        }
+        (Command::Cancel, PlanMode::Track(selection, TrackMode::Move(_))) => {
+            vec![Action::Restore(selection), Action::Unselect]
+        }
+        (Command::Cancel, PlanMode::Select(_)) => vec![Action::Unselect],
+        _ => vec![],
+    }
+}
+
+fn door_actions(command: Command, hover: &Hover, position: Option<Vec2>) -> Vec<Action> {
+    match (command, hover.door, hover.line) {
+        (Command::Click, None, Some(line)) => position
+            .map(|position| Action::AddDoor(line, position, DOOR_WIDTH))
+            .into_iter()
+            .collect(),
+        (Command::Delete, Some(door), _) => vec![Action::DeleteDoor(door)],
+        _ => vec![],
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Measurement {
+    start: Option<Vec2>,
+}
+
+// shown next to the mouse pointer
+#[derive(Resource, Default)]
+pub struct CursorHint(pub String);
+
+// an unfinished wall or move is cancelled so no tool inherits it
+fn switch_tool(
+    mode: Res<PlanMode>,
+    hover: Res<Hover>,
+    mut command_events: EventReader<Command>,
+    mut tool: ResMut<Tool>,
+    mut measurement: ResMut<Measurement>,
+    mut action_queue: ResMut<ActionQueue>,
+) {
+    for command in command_events.iter() {
+        let Some(new_tool) = Tool::from_command(*command) else {
+            continue;
+        };
+        if new_tool == *tool {
+            continue;
+        }
+        if let PlanMode::Track(_, _) = *mode {
+            action_queue.extend(tool.actions(Command::Cancel, &mode, &hover, None));
+        }
+        action_queue.push_back(Action::Unselect);
+        measurement.start = None;
+        *tool = new_tool;
+    }
+}
+
+fn switch_pivot(
+    mut command_events: EventReader<Command>,
+    mut pivot: ResMut<Pivot>,
+    mut notifications: EventWriter<Notification>,
+) {
+    for command in command_events.iter() {
+        if *command == Command::CyclePivot {
+            *pivot = pivot.next();
+            notifications.send(Notification::info(format!(
+                "Rotate, scale and mirror around the {}",
+                pivot.label()
+            )));
+        }
+    }
+}
+
+#[derive(Default)]
+struct Drag {
+    press: Option<(Vec<PointId>, Vec2)>,
+    point: Option<PointId>,
+}
+
+// the press itself selects as a click does, moving on tracks the point until
+// the button is released, which drops or merges it like a click while tracking
+fn drag_point(
+    tool: Res<Tool>,
+    mode: Res<PlanMode>,
+    hover: Res<Hover>,
+    cursor: Res<Cursor>,
+    pan: Res<CameraPan>,
+    focus: Res<InputFocus>,
+    pixel_size: Res<PixelSize>,
+    mouse_input: Res<Input<MouseButton>>,
+    interaction_query: Query<&Interaction>,
+    mut drag: Local<Drag>,
+    mut action_queue: ResMut<ActionQueue>,
+) {
+    if mouse_input.just_pressed(MouseButton::Left) {
+        *drag = Drag::default();
+        let over_ui = interaction_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None);
+        let tracking = matches!(*mode, PlanMode::Track(_, _));
+        let ready = *tool == Tool::Select && *focus == InputFocus::Plan && !pan.is_active();
+        if !ready || over_ui || tracking || hover.candidates.is_empty() {
+            return;
+        }
+        drag.press = cursor
+            .position
+            .map(|position| (hover.candidates.clone(), position));
+        return;
+    }
+    if mouse_input.just_released(MouseButton::Left) {
+        // cancelling the move already restored the point
+        if let (Some(id), PlanMode::Track(tracked, TrackMode::Move(_))) = (drag.point, *mode) {
+            if id == tracked {
+                action_queue.push_back(match hover.point {
+                    Some(hover) => Action::Merge(id, hover),
+                    None => Action::Select(id),
+                });
+            }
+        }
+        *drag = Drag::default();
+        return;
+    }
+    let (Some((candidates, start)), Some(position)) = (&drag.press, cursor.position) else {
+        return;
+    };
+    if position.distance(*start) < DRAG_THRESHOLD * **pixel_size {
+        return;
+    }
+    // clicking through overlapping points may have selected another candidate
+    let point = mode
+        .selection()
+        .filter(|selection| candidates.contains(selection))
+        .or(candidates.first().copied());
+    if let Some(point) = point {
+        action_queue.push_back(Action::Track(point));
+    }
+    drag.press = None;
+    drag.point = point;
+}
+
+fn measure_distance(
+    tool: Res<Tool>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    mut command_events: EventReader<Command>,
+    mut measurement: ResMut<Measurement>,
+    mut notifications: EventWriter<Notification>,
+) {
+    // the events are drained with any tool so none are left for the next one
+    for command in command_events.iter() {
+        if *tool != Tool::Measure {
+            continue;
+        }
+        match (command, measurement.start) {
+            (Command::Click, None) => measurement.start = cursor.track_position(&units),
+            (Command::Click, Some(start)) => {
+                if let Some(end) = cursor.track_position(&units) {
+                    let distance = units.format(start.distance(end));
+                    notifications.send(Notification::info(format!("Distance: {distance}")));
+                    measurement.start = None;
+                }
+            }
+            (Command::Cancel, _) => measurement.start = None,
+            _ => (),
+        }
+    }
+}
+
+fn inspect_room(
+    tool: Res<Tool>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    document: Res<PlanDocument>,
+    mut command_events: EventReader<Command>,
+    mut notifications: EventWriter<Notification>,
+) {
+    for command in command_events.iter() {
+        if *tool != Tool::Room || *command != Command::Click {
+            continue;
+        }
+        let room = cursor
+            .position
+            .and_then(|position| analysis::room_at(&document, position));
+        notifications.send(match room {
+            Some(room) => Notification::info(format!(
+                "Room with {} walls, area {}, perimeter {}",
+                room.points.len(),
+                units.format_area(room.area),
+                units.format(perimeter(&document, &room.points)),
+            )),
+            None => Notification::warning("There is no closed room at the cursor"),
+        });
+    }
+}
+
+// the console opens with the position filled in and takes the text
+fn place_label(
+    tool: Res<Tool>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    mut command_events: EventReader<Command>,
+    mut focus: ResMut<InputFocus>,
+    mut console: ResMut<Console>,
+) {
+    for command in command_events.iter() {
+        if *tool != Tool::Text || *command != Command::Click {
+            continue;
+        }
+        if let Some(position) = cursor.track_position(&units) {
+            console.prompt(format!(
+                "label {},{} ",
+                units.unit.format_number(position.x),
+                units.unit.format_number(position.y)
+            ));
+            *focus = InputFocus::Console;
+        }
+    }
+}
+
+fn perimeter(document: &PlanDocument, points: &[PointId]) -> f32 {
+    let positions: Vec<Vec2> = points
+        .iter()
+        .filter_map(|id| document.position(*id))
+        .collect();
+    let count = positions.len();
+    (0..count)
+        .map(|i| positions[i].distance(positions[(i + 1) % count]))
+        .sum()
+}
+
+fn update_cursor_hint(
+    tool: Res<Tool>,
+    mode: Res<PlanMode>,
+    hover: Res<Hover>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    measurement: Res<Measurement>,
+    document: Res<PlanDocument>,
+    mut rooms: Local<Option<Vec<analysis::Room>>>,
+    mut hint: ResMut<CursorHint>,
+) {
+    // the rooms are only found again once the plan was edited
+    if document.is_changed() {
+        *rooms = None;
+    }
+    let text = match (*tool, *mode) {
+        (Tool::Select, PlanMode::Track(_, TrackMode::Transform(kind, pivot, start))) => {
+            let line = hover.line.and_then(|line| document.line_positions(line));
+            let snap = matches!(cursor.mode, CursorMode::Coarse);
+            let transformation = cursor
+                .track_position(&units)
+                .and_then(|position| kind.transformation(pivot, start, position, line, snap));
+            match transformation {
+                Some(transformation) => transformation.to_string(),
+                None => kind.label().to_string(),
+            }
+        }
+        (Tool::Select, _) => String::new(),
+        (Tool::Wall, PlanMode::Track(id, TrackMode::Place)) => {
+            let length = document
+                .point(id)
+                .and_then(|point| point.lines().first().copied())
+                .and_then(|line| analysis::line_length(&document, line));
+            match length {
+                Some(length) => format!("Wall {}", units.format(length)),
+                None => "Wall".to_string(),
+            }
+        }
+        (Tool::Wall, _) => "Wall: click to start".to_string(),
+        (Tool::Measure, _) => match (measurement.start, cursor.track_position(&units)) {
+            (Some(start), Some(end)) => {
+                format!("Measure {}", units.format(start.distance(end)))
+            }
+            _ => "Measure: click the start".to_string(),
+        },
+        (Tool::Door, _) => match (hover.door, hover.line) {
+            (Some(_), _) => "Door: press delete to remove it".to_string(),
+            (None, Some(_)) => format!("Door {}", units.format(DOOR_WIDTH)),
+            (None, None) => "Door: click a wall".to_string(),
+        },
+        (Tool::Text, _) => match hover.label {
+            Some(_) => "Text: press delete to remove the label".to_string(),
+            None => "Text: click to place a label".to_string(),
+        },
+        (Tool::Room, _) => {
+            let rooms = rooms.get_or_insert_with(|| analysis::rooms(&document));
+            let room = cursor
+                .position
+                .and_then(|position| analysis::innermost(rooms, &document, position));
+            match room {
+                Some(room) => format!("Room {}", units.format_area(room.area)),
+                None => "Room: click inside a room".to_string(),
+            }
+        }
+    };
+    if hint.0 != text {
+        hint.0 = text;
+    }
+}
@@ -1,43 +1,162 @@
 use bevy::prelude::*;
 
 use crate::{
-    palette,
-    plan::{point::Point, PlanMode},
+    action::{Action, ActionQueue},
+    binding::{
+        config::{BindingContext, Bindings},
+        Command,
+    },
+    console::Console,
+    icon,
+    input::{Cursor, CursorMode, InputFocus},
+    inspector::{self, Field, Inspector, Row},
+    issues::IssueList,
+    menu::ContextMenu,
+    notification::{Level, Message, Messages},
+    plan::{document::PlanDocument, integrity::Element, PlanMode, Selection, TrackMode},
+    theme::Theme,
+    tool::{CursorHint, Tool},
+    underlay::Calibration,
+    units::UnitSystem,
     AppSet,
 };
 
+const MAX_LISTED_ISSUES: usize = 20;
+
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<UiAssets>()
             .add_startup_system(spawn_inspector_panel)
-            .add_system(update_inspector_text.in_set(AppSet::Ui));
+            .add_startup_system(spawn_prompt_panel)
+            .add_startup_system(spawn_console_panel)
+            .add_startup_system(spawn_issue_panel)
+            .add_startup_system(spawn_status_bar)
+            .add_startup_system(spawn_toast_panel)
+            .add_startup_system(spawn_log_panel)
+            .add_startup_system(spawn_toolbar)
+            .add_startup_system(spawn_cursor_hint)
+            .add_startup_system(spawn_context_menu)
+            // runs before the actions so the selection is updated in the same frame
+            .add_systems(
+                (
+                    select_issue,
+                    edit_inspector_field,
+                    select_tool,
+                    select_menu_entry,
+                )
+                    .in_set(AppSet::Binding),
+            )
+            .add_systems(
+                (
+                    update_inspector_panel,
+                    update_prompt_text,
+                    update_console_text,
+                    update_issue_panel,
+                    update_status_bar,
+                    update_toast_panel,
+                    update_log_panel,
+                    update_toolbar.after(apply_theme),
+                    update_cursor_hint,
+                    update_context_menu,
+                    apply_theme,
+                )
+                    .in_set(AppSet::Ui),
+            );
     }
 }
 
 #[derive(Resource)]
 struct UiAssets {
     font: Handle<Font>,
+    tool_icons: Vec<(Tool, Handle<Image>)>,
 }
 
 #[derive(Component)]
-struct InspectorText;
+struct InspectorPanel;
+
+#[derive(Component)]
+struct InspectorField(Field);
+
+#[derive(Component)]
+struct PromptText;
+
+#[derive(Component)]
+struct ConsoleText;
+
+#[derive(Component)]
+struct IssuePanel;
+
+#[derive(Component)]
+struct StatusText;
+
+#[derive(Component)]
+struct ToastPanel;
+
+#[derive(Component)]
+struct LogPanel;
+
+#[derive(Component)]
+struct IssueButton(usize);
+
+#[derive(Component)]
+struct Panel;
+
+#[derive(Component)]
+struct ToolButton(Tool);
+
+#[derive(Component)]
+struct ToolIcon;
+
+#[derive(Component)]
+struct ToolTip;
+
+#[derive(Component)]
+struct CursorHintText;
+
+#[derive(Component)]
+struct ContextMenuPanel;
+
+#[derive(Component)]
+struct MenuButton(usize);
 
 impl FromWorld for UiAssets {
     fn from_world(world: &mut World) -> Self {
         let asset_server: &AssetServer = world.resource();
-        Self {
-            font: asset_server.load("fonts/roboto_bold.ttf"),
-        }
+        let font = asset_server.load("fonts/roboto_bold.ttf");
+        let mut images: Mut<Assets<Image>> = world.resource_mut();
+        let tool_icons = Tool::ALL
+            .into_iter()
+            .map(|tool| (tool, images.add(icon::tool_icon(tool))))
+            .collect();
+        Self { font, tool_icons }
     }
 }
 
-fn spawn_inspector_panel(assets: Res<UiAssets>, mut commands: Commands) {
+fn spawn_inspector_panel(mut commands: Commands) {
+    let root = (
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect::new(Val::Auto, Val::Px(40.0), Val::Px(40.0), Val::Auto),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                gap: Size::all(Val::Px(4.0)),
+                ..default()
+            },
+            ..default()
+        },
+        InspectorPanel,
+    );
+    commands.spawn(root);
+}
+
+fn spawn_prompt_panel(assets: Res<UiAssets>, theme: Res<Theme>, mut commands: Commands) {
     let root = NodeBundle {
         style: Style {
             position_type: PositionType::Absolute,
-            position: UiRect::new(Val::Auto, Val::Px(40.0), Val::Px(40.0), Val::Auto),
+            position: UiRect::new(Val::Px(40.0), Val::Auto, Val::Auto, Val::Px(80.0)),
             ..default()
         },
         ..default()
@@ -45,44 +164,790 @@ fn spawn_inspector_panel(assets: Res<UiAssets>, mut commands: Commands) {
     let text = (
         TextBundle {
             text: Text::from_section(
-                "Uninitialized",
+                "",
                 TextStyle {
                     font: assets.font.clone(),
-                    font_size: 30.0,
-                    color: palette::LIGHT_WHITE,
+                    font_size: 24.0,
+                    color: theme.text,
                 },
             ),
             ..default()
         },
-        InspectorText,
+        PromptText,
     );
     commands.spawn(root).with_children(|builder| {
         builder.spawn(text);
     });
 }
 
-fn update_inspector_text(
+fn spawn_console_panel(assets: Res<UiAssets>, theme: Res<Theme>, mut commands: Commands) {
+    let root = NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect::new(Val::Px(40.0), Val::Auto, Val::Px(40.0), Val::Auto),
+            ..default()
+        },
+        ..default()
+    };
+    let text = (
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 24.0,
+                    color: theme.text,
+                },
+            ),
+            ..default()
+        },
+        ConsoleText,
+    );
+    commands.spawn(root).with_children(|builder| {
+        builder.spawn(text);
+    });
+}
+
+fn spawn_issue_panel(mut commands: Commands) {
+    let root = (
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect::new(Val::Auto, Val::Px(40.0), Val::Auto, Val::Px(80.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                gap: Size::all(Val::Px(4.0)),
+                ..default()
+            },
+            ..default()
+        },
+        IssuePanel,
+    );
+    commands.spawn(root);
+}
+
+fn spawn_status_bar(assets: Res<UiAssets>, theme: Res<Theme>, mut commands: Commands) {
+    let root = NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Auto, Val::Px(0.0)),
+            padding: UiRect::new(Val::Px(40.0), Val::Px(40.0), Val::Px(6.0), Val::Px(6.0)),
+            ..default()
+        },
+        background_color: theme.panel.into(),
+        ..default()
+    };
+    let text = (
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 16.0,
+                    color: theme.text,
+                },
+            ),
+            ..default()
+        },
+        StatusText,
+    );
+    commands.spawn((root, Panel)).with_children(|builder| {
+        builder.spawn(text);
+    });
+}
+
+fn spawn_toast_panel(mut commands: Commands) {
+    let root = (
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect::new(
+                    Val::Percent(30.0),
+                    Val::Percent(30.0),
+                    Val::Px(40.0),
+                    Val::Auto,
+                ),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                gap: Size::all(Val::Px(4.0)),
+                ..default()
+            },
+            ..default()
+        },
+        ToastPanel,
+    );
+    commands.spawn(root);
+}
+
+fn spawn_log_panel(assets: Res<UiAssets>, theme: Res<Theme>, mut commands: Commands) {
+    let root = NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect::new(Val::Px(40.0), Val::Auto, Val::Px(200.0), Val::Auto),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        background_color: theme.panel.into(),
+        visibility: Visibility::Hidden,
+        ..default()
+    };
+    let text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font: assets.font.clone(),
+            font_size: 16.0,
+            color: theme.text,
+        },
+    );
+    commands
+        .spawn((root, LogPanel, Panel))
+        .with_children(|builder| {
+            builder.spawn(text);
+        });
+}
+
+fn spawn_toolbar(assets: Res<UiAssets>, theme: Res<Theme>, mut commands: Commands) {
+    let root = NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect::new(Val::Px(40.0), Val::Auto, Val::Px(6.0), Val::Auto),
+            gap: Size::all(Val::Px(4.0)),
+            ..default()
+        },
+        ..default()
+    };
+    commands.spawn(root).with_children(|builder| {
+        for (tool, icon) in &assets.tool_icons {
+            let button = (
+                ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: theme.panel.into(),
+                    ..default()
+                },
+                ToolButton(*tool),
+            );
+            let icon = (
+                ImageBundle {
+                    style: Style {
+                        size: Size::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    image: UiImage::new(icon.clone()),
+                    background_color: theme.text.into(),
+                    ..default()
+                },
+                ToolIcon,
+            );
+            // the name of the tool shows below the button while hovering it
+            let tooltip = (
+                TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect::new(
+                            Val::Px(0.0),
+                            Val::Auto,
+                            Val::Percent(100.0),
+                            Val::Auto,
+                        ),
+                        margin: UiRect::top(Val::Px(4.0)),
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: theme.panel.into(),
+                    visibility: Visibility::Hidden,
+                    ..TextBundle::from_section(
+                        tool.label(),
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 16.0,
+                            color: theme.text,
+                        },
+                    )
+                },
+                ToolTip,
+                Panel,
+            );
+            builder.spawn(button).with_children(|builder| {
+                builder.spawn(icon);
+                builder.spawn(tooltip);
+            });
+        }
+    });
+}
+
+fn spawn_cursor_hint(assets: Res<UiAssets>, theme: Res<Theme>, mut commands: Commands) {
+    let text = (
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 16.0,
+                color: theme.text,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        }),
+        CursorHintText,
+    );
+    commands.spawn(text);
+}
+
+fn spawn_context_menu(theme: Res<Theme>, mut commands: Commands) {
+    let root = (
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(4.0)),
+                gap: Size::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: theme.panel.into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ContextMenuPanel,
+        Panel,
+    );
+    commands.spawn(root);
+}
+
+fn update_inspector_panel(
     mode: Res<PlanMode>,
-    point_query: Query<&Transform, With<Point>>,
-    mut text_query: Query<&mut Text, With<InspectorText>>,
+    units: Res<UnitSystem>,
+    document: Res<PlanDocument>,
+    inspector: Res<Inspector>,
+    assets: Res<UiAssets>,
+    theme: Res<Theme>,
+    panel_query: Query<Entity, With<InspectorPanel>>,
+    mut shown: Local<Vec<(Option<Field>, String)>>,
+    mut commands: Commands,
+) {
+    let rows: Vec<(Option<Field>, String)> = inspector::rows(&document, &mode, &units)
+        .into_iter()
+        .map(|row| match row {
+            Row::Text(text) => (None, text),
+            Row::Field(field) => {
+                let value = match &inspector.editing {
+                    Some(editing) if editing.field == field => match &editing.error {
+                        Some(error) => format!("{}_ ({error})", editing.text),
+                        None => format!("{}_", editing.text),
+                    },
+                    _ => mode.selection().map_or(String::new(), |selection| {
+                        field.format(&document, selection, &units)
+                    }),
+                };
+                (Some(field), format!("{}: {value}", field.label()))
+            }
+        })
+        .collect();
+    // rebuilding every frame would reset the button interactions
+    if *shown == rows && !theme.is_changed() {
+        return;
+    }
+    let panel = panel_query.single();
+    commands.entity(panel).despawn_descendants();
+    let style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 24.0,
+        color: theme.text,
+    };
+    commands.entity(panel).with_children(|builder| {
+        for (field, row) in &rows {
+            let text = TextBundle::from_section(row.clone(), style.clone());
+            let Some(field) = field else {
+                builder.spawn(text);
+                continue;
+            };
+            let button = (
+                ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: theme.panel.into(),
+                    ..default()
+                },
+                InspectorField(*field),
+            );
+            builder.spawn(button).with_children(|builder| {
+                builder.spawn(text);
+            });
+        }
+    });
+    *shown = rows;
+}
+
+fn edit_inspector_field(
+    mut button_query: Query<
+        (&Interaction, &InspectorField, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    theme: Res<Theme>,
+    mut focus: ResMut<InputFocus>,
+    mut inspector: ResMut<Inspector>,
+) {
+    for (interaction, field, mut color) in &mut button_query {
+        *color = match interaction {
+            Interaction::None => theme.panel,
+            _ => theme.panel_hover,
+        }
+        .into();
+        if *interaction == Interaction::Clicked && *focus != InputFocus::Console {
+            inspector.edit(field.0);
+            *focus = InputFocus::Inspector;
+        }
+    }
+}
+
+fn update_prompt_text(
+    calibration: Res<Calibration>,
+    units: Res<UnitSystem>,
+    mut text_query: Query<&mut Text, With<PromptText>>,
 ) {
     let mut text = text_query.single_mut();
-    if let Some(entity) = mode.selection() {
-        let transform = point_query.get(entity).unwrap();
-        text.sections[0].value = format!(
-            "({:.2}, {:.2})",
-            if transform.translation.x == -0.0 {
-                0.0
-            } else {
-                transform.translation.x
-            },
-            if transform.translation.y == -0.0 {
-                0.0
-            } else {
-                transform.translation.y
-            },
-        );
-    } else {
-        text.sections[0].value = "Nothing selected".to_string();
+    text.sections[0].value = match &*calibration {
+        Calibration::Inactive => String::new(),
+        Calibration::FirstPoint => "Calibration: click the first reference point".to_string(),
+        Calibration::SecondPoint(_) => "Calibration: click the second reference point".to_string(),
+        Calibration::Distance(_, _, distance) => {
+            let unit = units.unit.symbol();
+            format!("Calibration: real distance {distance}_ ({unit}, Enter to apply)")
+        }
+    };
+}
+
+fn update_console_text(
+    focus: Res<InputFocus>,
+    console: Res<Console>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    let mut text = text_query.single_mut();
+    text.sections[0].value = match *focus {
+        InputFocus::Console => {
+            let output: String = console.output.iter().map(|l| format!("{l}\n")).collect();
+            format!("{output}: {}_", console.input)
+        }
+        _ => String::new(),
+    };
+}
+
+fn update_issue_panel(
+    list: Res<IssueList>,
+    assets: Res<UiAssets>,
+    theme: Res<Theme>,
+    panel_query: Query<Entity, With<IssuePanel>>,
+    mut commands: Commands,
+) {
+    if !list.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let panel = panel_query.single();
+    commands.entity(panel).despawn_descendants();
+    if !list.open {
+        return;
+    }
+    let style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 20.0,
+        color: theme.text,
+    };
+    let title = match list.issues.len() {
+        0 => "No issues found".to_string(),
+        1 => "1 issue".to_string(),
+        count => format!("{count} issues"),
+    };
+    commands.entity(panel).with_children(|builder| {
+        builder.spawn(TextBundle::from_section(title, style.clone()));
+        for (index, issue) in list.issues.iter().enumerate().take(MAX_LISTED_ISSUES) {
+            let button = (
+                ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: theme.panel.into(),
+                    ..default()
+                },
+                IssueButton(index),
+            );
+            builder.spawn(button).with_children(|builder| {
+                builder.spawn(TextBundle::from_section(issue.to_string(), style.clone()));
+            });
+        }
+        if list.issues.len() > MAX_LISTED_ISSUES {
+            let more = format!("and {} more", list.issues.len() - MAX_LISTED_ISSUES);
+            builder.spawn(TextBundle::from_section(more, style.clone()));
+        }
+    });
+}
+
+fn select_issue(
+    list: Res<IssueList>,
+    document: Res<PlanDocument>,
+    theme: Res<Theme>,
+    mut button_query: Query<
+        (&Interaction, &IssueButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut action_queue: ResMut<ActionQueue>,
+    mut command_events: EventWriter<Command>,
+) {
+    for (interaction, button, mut color) in &mut button_query {
+        *color = match interaction {
+            Interaction::None => theme.panel,
+            _ => theme.panel_hover,
+        }
+        .into();
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let Some(issue) = list.issues.get(button.0) else {
+            continue;
+        };
+        // lines cannot be selected yet, their first point stands in for them
+        let point = issue.elements.iter().find_map(|element| match *element {
+            Element::Point(id) => Some(id),
+            Element::Line(id) => document.line(id).map(|line| line.point_a),
+        });
+        if let Some(point) = point {
+            action_queue.push_back(Action::Select(point));
+            command_events.send(Command::FrameSelection);
+        }
+    }
+}
+
+fn update_status_bar(
+    tool: Res<Tool>,
+    mode: Res<PlanMode>,
+    selection: Res<Selection>,
+    cursor: Res<Cursor>,
+    units: Res<UnitSystem>,
+    focus: Res<InputFocus>,
+    bindings: Res<Bindings>,
+    mut text_query: Query<&mut Text, With<StatusText>>,
+) {
+    let state = match *mode {
+        PlanMode::Default => "Default".to_string(),
+        PlanMode::Select(id) => match selection.points.len() {
+            0 | 1 => format!("Select point {id}"),
+            count => format!("Select point {id} and {} more", count - 1),
+        },
+        PlanMode::Track(id, TrackMode::Place) => format!("Place point {id}"),
+        PlanMode::Track(id, TrackMode::Move(_)) => format!("Move point {id}"),
+        PlanMode::Track(id, TrackMode::Transform(kind, _, _)) => match selection.points.len() {
+            0 | 1 => format!("{} point {id}", kind.label()),
+            count => format!("{} {count} points", kind.label()),
+        },
+    };
+    let state = format!("{} tool, {state}", tool.label());
+    let position = match cursor.position {
+        Some(position) => format!(
+            "({}, {})",
+            units.format(position.x),
+            units.format(position.y)
+        ),
+        None => "-".to_string(),
+    };
+    let snap = match cursor.mode {
+        CursorMode::Coarse => format!("snap {} (hold Alt for fine)", units.format(units.step)),
+        CursorMode::Fine => format!("fine snap {}", units.format(units.fine_step)),
+    };
+    // generated from the loaded bindings so the hints follow the configuration
+    let hints = match *focus {
+        InputFocus::Plan => {
+            let mut shown = Vec::new();
+            for binding in bindings.active(BindingContext::from_state(*tool, &mode)) {
+                if !shown
+                    .iter()
+                    .any(|(sequence, _)| *sequence == binding.sequence)
+                {
+                    shown.push((binding.sequence, binding.command));
+                }
+            }
+            shown
+                .iter()
+                .map(|(sequence, command)| format!("{sequence} {}", command.name()))
+                .collect::<Vec<_>>()
+                .join("   ")
+        }
+        _ => "Enter apply   Escape cancel".to_string(),
+    };
+    let status = format!(
+        "{state}   |   {position}   |   {snap}   |   unit {}\n{hints}",
+        units.unit.symbol()
+    );
+    let mut text = text_query.single_mut();
+    if text.sections[0].value != status {
+        text.sections[0].value = status;
+    }
+}
+
+fn level_color(level: Level, theme: &Theme) -> Color {
+    match level {
+        Level::Info => theme.panel,
+        Level::Warning => theme.warning,
+        Level::Error => theme.error,
+    }
+}
+
+fn update_toast_panel(
+    messages: Res<Messages>,
+    time: Res<Time>,
+    assets: Res<UiAssets>,
+    theme: Res<Theme>,
+    panel_query: Query<Entity, With<ToastPanel>>,
+    mut shown: Local<Vec<f32>>,
+    mut commands: Commands,
+) {
+    let toasts: Vec<&Message> = messages.toasts(time.elapsed_seconds()).collect();
+    let times: Vec<f32> = toasts.iter().map(|message| message.time).collect();
+    if *shown == times && !theme.is_changed() {
+        return;
+    }
+    let panel = panel_query.single();
+    commands.entity(panel).despawn_descendants();
+    commands.entity(panel).with_children(|builder| {
+        for message in toasts {
+            let toast = NodeBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: level_color(message.level, &theme).into(),
+                ..default()
+            };
+            let text = TextBundle::from_section(
+                message.text.clone(),
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 20.0,
+                    color: theme.text,
+                },
+            );
+            builder.spawn(toast).with_children(|builder| {
+                builder.spawn(text);
+            });
+        }
+    });
+    *shown = times;
+}
+
+fn update_log_panel(
+    messages: Res<Messages>,
+    mut panel_query: Query<(&mut Visibility, &Children), With<LogPanel>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !messages.is_changed() {
+        return;
+    }
+    let (mut visibility, children) = panel_query.single_mut();
+    *visibility = match messages.log_open {
+        true => Visibility::Inherited,
+        false => Visibility::Hidden,
+    };
+    let Ok(mut text) = text_query.get_mut(children[0]) else {
+        return;
+    };
+    let lines: Vec<String> = messages
+        .visible_log()
+        .iter()
+        .map(|message| {
+            let level = match message.level {
+                Level::Info => "info",
+                Level::Warning => "warning",
+                Level::Error => "error",
+            };
+            format!("{} {level}: {}", message.timestamp(), message.text)
+        })
+        .collect();
+    text.sections[0].value = match lines.is_empty() {
+        true => "No messages".to_string(),
+        false => lines.join("\n"),
+    };
+}
+
+fn select_tool(
+    button_query: Query<(&Interaction, &ToolButton), Changed<Interaction>>,
+    mut command_events: EventWriter<Command>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction == Interaction::Clicked {
+            command_events.send(button.0.command());
+        }
+    }
+}
+
+// the icon of the active tool takes the selection color
+fn update_toolbar(
+    tool: Res<Tool>,
+    theme: Res<Theme>,
+    mut button_query: Query<(&Interaction, &ToolButton, &Children, &mut BackgroundColor)>,
+    mut icon_query: Query<&mut BackgroundColor, (With<ToolIcon>, Without<ToolButton>)>,
+    mut tooltip_query: Query<&mut Visibility, With<ToolTip>>,
+) {
+    for (interaction, button, children, mut color) in &mut button_query {
+        let background = match interaction {
+            Interaction::None => theme.panel,
+            _ => theme.panel_hover,
+        };
+        if color.0 != background {
+            *color = background.into();
+        }
+        let icon_color = match button.0 == *tool {
+            true => theme.selection,
+            false => theme.text,
+        };
+        let tooltip_visibility = match interaction {
+            Interaction::Hovered => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+        for child in children {
+            if let Ok(mut color) = icon_query.get_mut(*child) {
+                if color.0 != icon_color {
+                    *color = icon_color.into();
+                }
+            }
+            if let Ok(mut visibility) = tooltip_query.get_mut(*child) {
+                if *visibility != tooltip_visibility {
+                    *visibility = tooltip_visibility;
+                }
+            }
+        }
+    }
+}
+
+fn update_cursor_hint(
+    hint: Res<CursorHint>,
+    window_query: Query<&Window>,
+    mut text_query: Query<(&mut Text, &mut Style, &mut Visibility), With<CursorHintText>>,
+) {
+    let (mut text, mut style, mut visibility) = text_query.single_mut();
+    let cursor = window_query.single().cursor_position();
+    let (Some(cursor), false) = (cursor, hint.0.is_empty()) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    if text.sections[0].value != hint.0 {
+        text.sections[0].value = hint.0.clone();
+    }
+    // window positions start at the bottom left like the ui offsets below
+    style.position = UiRect::new(
+        Val::Px(cursor.x + 16.0),
+        Val::Auto,
+        Val::Auto,
+        Val::Px(cursor.y - 32.0),
+    );
+}
+
+fn update_context_menu(
+    menu: Res<ContextMenu>,
+    theme: Res<Theme>,
+    assets: Res<UiAssets>,
+    window_query: Query<&Window>,
+    mut panel_query: Query<(Entity, &mut Style, &mut Visibility), With<ContextMenuPanel>>,
+    mut commands: Commands,
+) {
+    if !menu.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let (panel, mut style, mut visibility) = panel_query.single_mut();
+    commands.entity(panel).despawn_descendants();
+    let Some(menu) = &menu.open else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    // window positions start at the bottom, the menu hangs down from the cursor
+    let height = window_query.single().height();
+    style.position = UiRect::new(
+        Val::Px(menu.screen_position.x),
+        Val::Auto,
+        Val::Px(height - menu.screen_position.y),
+        Val::Auto,
+    );
+    let style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 16.0,
+        color: theme.text,
+    };
+    commands.entity(panel).with_children(|builder| {
+        builder.spawn(TextBundle::from_section(menu.title.clone(), style.clone()));
+        for (index, entry) in menu.entries.iter().enumerate() {
+            let label = match &entry.shortcut {
+                Some(shortcut) => format!("{}   {shortcut}", entry.label),
+                None => entry.label.to_string(),
+            };
+            let button = (
+                ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: theme.panel.into(),
+                    ..default()
+                },
+                MenuButton(index),
+            );
+            builder.spawn(button).with_children(|builder| {
+                builder.spawn(TextBundle::from_section(label, style.clone()));
+            });
+        }
+    });
+}
+
+fn select_menu_entry(
+    theme: Res<Theme>,
+    mut button_query: Query<
+        (&Interaction, &MenuButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut menu: ResMut<ContextMenu>,
+    mut focus: ResMut<InputFocus>,
+    mut action_queue: ResMut<ActionQueue>,
+    mut command_events: EventWriter<Command>,
+) {
+    for (interaction, button, mut color) in &mut button_query {
+        *color = match interaction {
+            Interaction::None => theme.panel,
+            _ => theme.panel_hover,
+        }
+        .into();
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let Some((actions, command)) = menu.open.as_ref().and_then(|menu| menu.run(button.0))
+        else {
+            continue;
+        };
+        action_queue.extend(actions);
+        command_events.send(command);
+        menu.open = None;
+        *focus = InputFocus::Plan;
+    }
+}
+
+// the rebuilt panels pick up the theme on their own, this recolors the static ones
+fn apply_theme(
+    theme: Res<Theme>,
+    mut text_query: Query<&mut Text>,
+    mut panel_query: Query<&mut BackgroundColor, With<Panel>>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    for mut text in &mut text_query {
+        for section in &mut text.sections {
+            section.style.color = theme.text;
+        }
+    }
+    for mut color in &mut panel_query {
+        *color = theme.panel.into();
     }
 }
@@ -0,0 +1,199 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::{
+    binding::Command,
+    camera::CameraPan,
+    input::{Cursor, InputFocus},
+    notification::Notification,
+    plan::BASE_PRIORITY,
+    units::UnitSystem,
+    AppSet,
+};
+
+const UNDERLAY_PRIORITY: f32 = BASE_PRIORITY - 1.0;
+const UNDERLAY_SCALE: f32 = 0.01;
+const UNDERLAY_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+const DEFAULT_OPACITY: f32 = 0.5;
+const OPACITY_STEP: f32 = 0.1;
+
+pub struct UnderlayPlugin;
+
+impl Plugin for UnderlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Calibration>()
+            .add_system(load_underlay.in_set(AppSet::Input))
+            // these react to the commands bound in the same frame
+            .add_systems((update_underlay_opacity, calibrate_underlay).in_set(AppSet::Action));
+    }
+}
+
+#[derive(Component)]
+pub struct Underlay;
+
+#[derive(Resource, Default)]
+pub enum Calibration {
+    #[default]
+    Inactive,
+    FirstPoint,
+    SecondPoint(Vec2),
+    Distance(Vec2, Vec2, String),
+}
+
+fn load_underlay(
+    mut events: EventReader<FileDragAndDrop>,
+    query: Query<Entity, With<Underlay>>,
+    asset_server: Res<AssetServer>,
+    mut notifications: EventWriter<Notification>,
+    mut commands: Commands,
+) {
+    for event in events.iter() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = event else {
+            continue;
+        };
+        let is_image = path_buf
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| UNDERLAY_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+            .unwrap_or(false);
+        if !is_image {
+            continue;
+        }
+        for entity in &query {
+            commands.entity(entity).despawn();
+        }
+        commands.spawn((
+            SpriteBundle {
+                texture: asset_server.load(path_buf.clone()),
+                sprite: Sprite {
+                    color: Color::WHITE.with_a(DEFAULT_OPACITY),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec2::ZERO.extend(UNDERLAY_PRIORITY))
+                    .with_scale(Vec3::new(UNDERLAY_SCALE, UNDERLAY_SCALE, 1.0)),
+                ..default()
+            },
+            Underlay,
+        ));
+        notifications.send(Notification::info(format!(
+            "Imported underlay {}",
+            path_buf.display()
+        )));
+    }
+}
+
+fn update_underlay_opacity(
+    mut command_events: EventReader<Command>,
+    mut query: Query<&mut Sprite, With<Underlay>>,
+) {
+    for command in command_events.iter() {
+        let step = match command {
+            Command::IncreaseOpacity => OPACITY_STEP,
+            Command::DecreaseOpacity => -OPACITY_STEP,
+            _ => continue,
+        };
+        for mut sprite in &mut query {
+            let opacity = (sprite.color.a() + step).clamp(0.0, 1.0);
+            sprite.color.set_a(opacity);
+        }
+    }
+}
+
+fn calibrate_underlay(
+    cursor: Res<Cursor>,
+    pan: Res<CameraPan>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    units: Res<UnitSystem>,
+    mut command_events: EventReader<Command>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut query: Query<&mut Transform, With<Underlay>>,
+    mut calibration: ResMut<Calibration>,
+    mut focus: ResMut<InputFocus>,
+    mut notifications: EventWriter<Notification>,
+) {
+    let typed: String = characters.iter().map(|event| event.char).collect();
+    let start = command_events
+        .iter()
+        .any(|command| *command == Command::Calibrate);
+    let Ok(mut transform) = query.get_single_mut() else {
+        if *focus == InputFocus::Calibration {
+            *calibration = Calibration::Inactive;
+            *focus = InputFocus::Plan;
+        }
+        if start {
+            notifications.send(Notification::warning(
+                "Drop an image on the window before calibrating",
+            ));
+        }
+        return;
+    };
+    if *focus == InputFocus::Plan && start {
+        *calibration = Calibration::FirstPoint;
+        *focus = InputFocus::Calibration;
+        return;
+    }
+    if *focus != InputFocus::Calibration {
+        return;
+    }
+    // the key that ends calibrating is used up so nothing later reacts to it
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        *calibration = Calibration::Inactive;
+        *focus = InputFocus::Plan;
+        return;
+    }
+    let clicked = mouse_input.just_pressed(MouseButton::Left) && !pan.is_active();
+    match &mut *calibration {
+        Calibration::FirstPoint => {
+            if let Some(position) = cursor.position.filter(|_| clicked) {
+                *calibration = Calibration::SecondPoint(position);
+            }
+        }
+        Calibration::SecondPoint(first) => {
+            if let Some(position) = cursor.position.filter(|_| clicked) {
+                if position != *first {
+                    *calibration = Calibration::Distance(*first, position, String::new());
+                }
+            }
+        }
+        Calibration::Distance(first, second, text) => {
+            text.extend(typed.chars().filter(|c| !c.is_control()));
+            if keyboard_input.just_pressed(KeyCode::Back) {
+                text.pop();
+            }
+            if keyboard_input.clear_just_pressed(KeyCode::Return) {
+                if let Some(distance) = units.parse(text).filter(|d| *d > 0.0) {
+                    calibrate(&mut transform, *first, *second, distance);
+                    *calibration = Calibration::Inactive;
+                    *focus = InputFocus::Plan;
+                    notifications.send(Notification::info("Calibrated the underlay"));
+                } else {
+                    notifications.send(Notification::warning(format!(
+                        "Invalid calibration distance `{text}`"
+                    )));
+                }
+            }
+        }
+        Calibration::Inactive => {
+            *focus = InputFocus::Plan;
+        }
+    }
+}
+
+// scales the image around the first point and rotates the reference
+// segment onto the closest axis, as traced walls are usually orthogonal
+fn calibrate(transform: &mut Transform, first: Vec2, second: Vec2, distance: f32) {
+    let segment = second - first;
+    let factor = distance / segment.length();
+    let angle = segment.y.atan2(segment.x);
+    let correction = (angle / FRAC_PI_2).round() * FRAC_PI_2 - angle;
+    let rotation = Quat::from_rotation_z(correction);
+    let offset = transform.translation.truncate() - first;
+    let translation = first + rotation.mul_vec3(offset.extend(0.0)).truncate() * factor;
+    transform.translation.x = translation.x;
+    transform.translation.y = translation.y;
+    transform.rotation = rotation * transform.rotation;
+    transform.scale.x *= factor;
+    transform.scale.y *= factor;
+}
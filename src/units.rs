@@ -0,0 +1,377 @@
+use std::fs;
+
+use bevy::prelude::*;
+
+pub use crate::plan::unit::Unit;
+use crate::{
+    binding::Command,
+    plan::unit::{INCHES_PER_FOOT, MAX_DECIMALS, METERS_PER_INCH},
+    AppSet,
+};
+
+const UNITS_PATH: &str = "assets/config/units.cfg";
+// coarse and fine snap steps in meters
+const METRIC_STEPS: (f32, f32) = (0.1, 0.01);
+const IMPERIAL_STEPS: (f32, f32) = (METERS_PER_INCH, METERS_PER_INCH / 4.0);
+
+pub struct UnitsPlugin;
+
+impl Plugin for UnitsPlugin {
+    fn build(&self, app: &mut App) {
+        let (units, warnings) = UnitSystem::load();
+        for warning in warnings {
+            warn!("{warning}");
+        }
+        app.insert_resource(units)
+            .add_system(cycle_unit.in_set(AppSet::Action));
+    }
+}
+
+// all plan coordinates are stored in meters, this only affects
+// how they are snapped, displayed and typed
+#[derive(Resource, Clone, Debug)]
+pub struct UnitSystem {
+    pub unit: Unit,
+    pub step: f32,
+    pub fine_step: f32,
+    // the steps of the other system are kept while it is not in use
+    other_steps: (f32, f32),
+}
+
+impl Default for UnitSystem {
+    fn default() -> Self {
+        Self::metric(Unit::Meters)
+    }
+}
+
+impl UnitSystem {
+    pub fn metric(unit: Unit) -> Self {
+        Self {
+            unit,
+            step: METRIC_STEPS.0,
+            fine_step: METRIC_STEPS.1,
+            other_steps: IMPERIAL_STEPS,
+        }
+    }
+
+    pub fn imperial() -> Self {
+        Self {
+            unit: Unit::FeetInches,
+            step: IMPERIAL_STEPS.0,
+            fine_step: IMPERIAL_STEPS.1,
+            other_steps: METRIC_STEPS,
+        }
+    }
+
+    // the warnings describe an unreadable file or invalid lines, which are skipped
+    pub fn load() -> (Self, Vec<String>) {
+        match fs::read_to_string(UNITS_PATH) {
+            Ok(source) => Self::parse_config(&source),
+            Err(error) => (
+                Self::default(),
+                vec![format!(
+                    "Using the default units, could not read {UNITS_PATH}: {error}"
+                )],
+            ),
+        }
+    }
+
+    // `unit = <symbol>`, `snap = <length>` and `fine-snap = <length>`, the
+    // lengths are read in the unit set above them
+    pub fn parse_config(source: &str) -> (Self, Vec<String>) {
+        let mut units = Self::default();
+        let mut warnings = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Err(error) = units.parse_config_line(line) {
+                warnings.push(format!(
+                    "Invalid units line in {UNITS_PATH}, line {}: {error}",
+                    index + 1
+                ));
+            }
+        }
+        (units, warnings)
+    }
+
+    fn parse_config_line(&mut self, line: &str) -> Result<(), String> {
+        let (key, value) = line
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or("expected `key = value`")?;
+        match key {
+            "unit" => {
+                let unit = Unit::parse(value).ok_or(format!("unknown unit `{value}`"))?;
+                self.set_unit(unit);
+                Ok(())
+            }
+            "snap" => self.set_snap(self.parse_snap(value)?, None),
+            "fine-snap" => self.set_snap(self.step, Some(self.parse_snap(value)?)),
+            _ => Err(format!("unknown key `{key}`")),
+        }
+    }
+
+    pub fn set_unit(&mut self, unit: Unit) {
+        if unit.is_imperial() != self.unit.is_imperial() {
+            let steps = (self.step, self.fine_step);
+            (self.step, self.fine_step) = self.other_steps;
+            self.other_steps = steps;
+        }
+        self.unit = unit;
+    }
+
+    pub fn parse_snap(&self, text: &str) -> Result<f32, String> {
+        self.parse(text)
+            .filter(|length| *length > 0.0)
+            .ok_or_else(|| format!("invalid snap length `{text}`"))
+    }
+
+    // sets the steps of the system in use, without a fine step the current
+    // one is kept unless it is larger than the new step
+    pub fn set_snap(&mut self, step: f32, fine_step: Option<f32>) -> Result<(), String> {
+        let fine_step = fine_step.unwrap_or(self.fine_step.min(step));
+        if fine_step > step {
+            return Err(format!(
+                "the fine snap {} is larger than the snap {}",
+                self.format(fine_step),
+                self.format(step),
+            ));
+        }
+        (self.step, self.fine_step) = (step, fine_step);
+        Ok(())
+    }
+
+    pub fn snap(&self, meters: f32, fine: bool) -> f32 {
+        let step = if fine { self.fine_step } else { self.step };
+        (meters / step).round() * step
+    }
+
+    pub fn format(&self, meters: f32) -> String {
+        if self.unit.is_imperial() {
+            return self.format_imperial(meters);
+        }
+        let step = self.fine_step / self.unit.factor();
+        let decimals = (-step.log10() - 1e-3).ceil().clamp(0.0, MAX_DECIMALS) as usize;
+        let offset = 10_f32.powi(decimals as i32);
+        let value = (meters / self.unit.factor() * offset).round() / offset;
+        // avoids printing "-0.00"
+        let value = if value == 0.0 { 0.0 } else { value };
+        format!("{value:.decimals$} {}", self.unit.symbol())
+    }
+
+    pub fn format_area(&self, square_meters: f32) -> String {
+        let (factor, symbol) = match self.unit.is_imperial() {
+            true => ((METERS_PER_INCH * INCHES_PER_FOOT as f32).powi(2), "ft"),
+            false => (self.unit.factor().powi(2), self.unit.symbol()),
+        };
+        format!("{:.2} {symbol}²", square_meters / factor)
+    }
+
+    fn format_imperial(&self, meters: f32) -> String {
+        let denominator = (METERS_PER_INCH / self.fine_step).round().max(1.0) as u32;
+        let total = (meters.abs() / METERS_PER_INCH * denominator as f32).round() as u32;
+        let sign = if meters < 0.0 && total > 0 { "-" } else { "" };
+        let feet = total / (INCHES_PER_FOOT * denominator);
+        let remainder = total % (INCHES_PER_FOOT * denominator);
+        let inches = remainder / denominator;
+        let numerator = remainder % denominator;
+        let fraction = if numerator > 0 {
+            let divisor = gcd(numerator, denominator);
+            format!(" {}/{}", numerator / divisor, denominator / divisor)
+        } else {
+            String::new()
+        };
+        format!("{sign}{feet}' {inches}{fraction}\"")
+    }
+
+    pub fn parse(&self, text: &str) -> Option<f32> {
+        let text = text.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, text),
+        };
+        let meters = if text.contains(['\'', '"']) {
+            parse_imperial(text)?
+        } else {
+            parse_metric(text, self.unit)?
+        };
+        Some(if negative { -meters } else { meters })
+    }
+}
+
+fn parse_metric(text: &str, default: Unit) -> Option<f32> {
+    for unit in [Unit::Millimeters, Unit::Centimeters, Unit::Meters] {
+        if let Some(number) = text.strip_suffix(unit.symbol()) {
+            return Some(parse_number(number)? * unit.factor());
+        }
+    }
+    Some(parse_number(text)? * default.factor())
+}
+
+// accepts 12', 12' 6", 12'6-1/2", 6 1/2" and 1/2"
+fn parse_imperial(text: &str) -> Option<f32> {
+    let (feet, inches) = match text.split_once('\'') {
+        Some((feet, inches)) => (parse_number(feet)?, inches),
+        None => (0.0, text),
+    };
+    let inches = inches.trim().trim_end_matches('"').trim();
+    let inches = inches.strip_prefix('-').unwrap_or(inches).trim();
+    let inches = match inches.is_empty() {
+        true => 0.0,
+        false => parse_inches(inches)?,
+    };
+    Some((feet * INCHES_PER_FOOT as f32 + inches) * METERS_PER_INCH)
+}
+
+fn parse_inches(text: &str) -> Option<f32> {
+    let (whole, fraction) = match text.split_once([' ', '-']) {
+        Some((whole, fraction)) => (parse_number(whole)?, fraction.trim()),
+        None if text.contains('/') => (0.0, text),
+        None => return parse_number(text),
+    };
+    let (numerator, denominator) = fraction.split_once('/')?;
+    let denominator = parse_number(denominator).filter(|d| *d != 0.0)?;
+    Some(whole + parse_number(numerator)? / denominator)
+}
+
+fn parse_number(text: &str) -> Option<f32> {
+    text.trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+fn cycle_unit(mut command_events: EventReader<Command>, mut units: ResMut<UnitSystem>) {
+    for command in command_events.iter() {
+        if *command == Command::CycleUnit {
+            let next = units.unit.next();
+            units.set_unit(next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(value: Option<f32>, expected: f32) {
+        let value = value.unwrap();
+        assert!((value - expected).abs() < 1e-5, "{value} != {expected}");
+    }
+
+    #[test]
+    fn parse_metric_lengths() {
+        let units = UnitSystem::metric(Unit::Centimeters);
+        assert_near(units.parse("250"), 2.5);
+        assert_near(units.parse("1.5m"), 1.5);
+        assert_near(units.parse("1.5 m"), 1.5);
+        assert_near(units.parse("40mm"), 0.04);
+        assert_near(units.parse(" - 12cm "), -0.12);
+        assert_eq!(units.parse(""), None);
+        assert_eq!(units.parse("12km"), None);
+        assert_eq!(units.parse("inf"), None);
+    }
+
+    #[test]
+    fn parse_imperial_lengths() {
+        let units = UnitSystem::metric(Unit::Meters);
+        let foot = METERS_PER_INCH * 12.0;
+        assert_near(units.parse("12'"), 12.0 * foot);
+        assert_near(units.parse("12' 6\""), 12.0 * foot + 6.0 * METERS_PER_INCH);
+        assert_near(
+            units.parse("12'6-1/2\""),
+            12.0 * foot + 6.5 * METERS_PER_INCH,
+        );
+        assert_near(units.parse("6 1/2\""), 6.5 * METERS_PER_INCH);
+        assert_near(units.parse("-1/2\""), -0.5 * METERS_PER_INCH);
+        assert_eq!(units.parse("1/0\""), None);
+        assert_eq!(units.parse("a'"), None);
+        // bare numbers are inches once the unit is imperial
+        assert_near(UnitSystem::imperial().parse("3"), 3.0 * METERS_PER_INCH);
+    }
+
+    #[test]
+    fn format_round_trips_through_parse() {
+        for units in [
+            UnitSystem::metric(Unit::Meters),
+            UnitSystem::metric(Unit::Millimeters),
+            UnitSystem::imperial(),
+        ] {
+            // formatting rounds to the fine step
+            for meters in [0.0, 0.25, -1.27, 3.81] {
+                let text = units.format(meters);
+                let parsed = units.parse(text.trim_end_matches(units.unit.symbol()));
+                assert!((parsed.unwrap() - meters).abs() <= units.fine_step / 2.0 + 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn format_lengths() {
+        assert_eq!(UnitSystem::metric(Unit::Meters).format(-0.001), "0.00 m");
+        assert_eq!(
+            UnitSystem::metric(Unit::Centimeters).format(1.234),
+            "123 cm"
+        );
+        let inches = 12.0 * 5.0 + 3.25;
+        assert_eq!(
+            UnitSystem::imperial().format(inches * METERS_PER_INCH),
+            "5' 3 1/4\""
+        );
+    }
+
+    #[test]
+    fn set_unit_switches_steps() {
+        let mut units = UnitSystem::default();
+        units.set_unit(Unit::Millimeters);
+        assert_eq!(units.step, 0.1);
+        units.set_unit(Unit::FeetInches);
+        assert_eq!(units.step, METERS_PER_INCH);
+        assert_near(Some(units.snap(0.03, false)), METERS_PER_INCH);
+        assert_eq!(Unit::parse("ft-in"), Some(Unit::FeetInches));
+        assert_eq!(Unit::parse("yd"), None);
+    }
+
+    #[test]
+    fn snap_steps_are_kept_per_system() {
+        let mut units = UnitSystem::default();
+        units.set_snap(0.05, None).unwrap();
+        assert_eq!((units.step, units.fine_step), (0.05, 0.01));
+        units.set_snap(0.005, None).unwrap();
+        assert_eq!((units.step, units.fine_step), (0.005, 0.005));
+        assert!(units.set_snap(0.01, Some(0.02)).is_err());
+        units.set_unit(Unit::FeetInches);
+        assert_eq!(units.step, METERS_PER_INCH);
+        units.set_unit(Unit::Centimeters);
+        assert_eq!(units.step, 0.005);
+        assert!(units.parse_snap("0").is_err());
+        assert!(units.parse_snap("-1").is_err());
+    }
+
+    #[test]
+    fn config_sets_unit_and_snap() {
+        let source = "unit = cm # comment\nsnap = 5\nfine-snap = 1mm\n";
+        let (units, warnings) = UnitSystem::parse_config(source);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(units.unit, Unit::Centimeters);
+        assert_near(Some(units.step), 0.05);
+        assert_near(Some(units.fine_step), 0.001);
+        let source = "unit = yd\nsnap = 1mm\nfine-snap = 1cm\nsnap\ngrid = 1\n";
+        let (units, warnings) = UnitSystem::parse_config(source);
+        assert_eq!(warnings.len(), 4, "{warnings:?}");
+        assert!(warnings[0].ends_with("line 1: unknown unit `yd`"));
+        assert!(
+            warnings[1].ends_with("line 3: the fine snap 0.010 m is larger than the snap 0.001 m")
+        );
+        assert_near(Some(units.step), 0.001);
+    }
+}